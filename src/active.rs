@@ -0,0 +1,487 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{Context, Result};
+
+use crate::{SshAccount, SshManager};
+
+// A `# gam ACTIVE START [host]` block as found in ~/.ssh/config
+pub(crate) struct ActiveBlock {
+    pub(crate) host: String,
+    pub(crate) identity_files: Vec<String>,
+}
+
+// "# gam ACTIVE START [github.com]" -> "github.com"; legacy ssh-manager markers count too
+fn marker_host(line: &str, kind: &str) -> Option<String> {
+    let rest = line.strip_prefix("# gam ").or_else(|| line.strip_prefix("# ssh-manager "))?;
+    let host = rest.strip_prefix(kind)?.trim().strip_prefix('[')?.strip_suffix(']')?;
+    Some(host.to_string())
+}
+
+pub(crate) fn parse_active_blocks(content: &str) -> Vec<ActiveBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<ActiveBlock> = None;
+    for line in content.lines() {
+        let text = line.trim();
+        if let Some(host) = marker_host(text, "ACTIVE START") {
+            // A block missing its END marker still applies up to the next one
+            blocks.extend(current.replace(ActiveBlock { host, identity_files: Vec::new() }));
+        } else if marker_host(text, "ACTIVE END").is_some() {
+            blocks.extend(current.take());
+        } else if let Some(block) = current.as_mut() {
+            // ssh_config accepts "Keyword value", "Keyword=value" and "Keyword = value"
+            let Some((keyword, value)) = text.split_once(|c: char| c.is_whitespace() || c == '=') else {
+                continue;
+            };
+            let value = value.trim_start();
+            let value = value.strip_prefix('=').unwrap_or(value).trim();
+            if keyword.eq_ignore_ascii_case("IdentityFile") {
+                block.identity_files.push(value.trim_matches('"').to_string());
+            }
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+impl SshManager {
+    pub(crate) fn active_blocks(&self) -> Vec<ActiveBlock> {
+        let content = fs::read_to_string(self.ssh_dir.join("config")).unwrap_or_default();
+        parse_active_blocks(&content)
+    }
+
+    fn identity_path(&self, identity_file: &str) -> PathBuf {
+        match identity_file.strip_prefix("~/") {
+            Some(rest) => self.ssh_dir.parent().map_or_else(|| PathBuf::from(identity_file), |home| home.join(rest)),
+            None => self.ssh_dir.join(identity_file),
+        }
+    }
+
+    // Whether an IdentityFile value names one of the account's keys
+    pub(crate) fn owns_identity_file(&self, account: &SshAccount, identity_file: &str) -> bool {
+        let path = self.identity_path(identity_file);
+        Self::account_keys(account).iter().any(|(_, file, _)| self.ssh_dir.join(file) == path)
+    }
+
+    // Account whose key an active block offers
+    pub(crate) fn account_for_active_block(&self, block: &ActiveBlock) -> Option<&SshAccount> {
+        block.identity_files.iter().find_map(|identity_file| {
+            self.config.accounts.values().find(|account| self.owns_identity_file(account, identity_file))
+        })
+    }
+
+    // Configs from before `active_hosts` only had `current_account`; read the real mapping
+    // from the active blocks instead. Returns whether the config changed.
+    pub(crate) fn migrate_active_hosts(&mut self) -> bool {
+        if self.config.current_account.take().is_none() {
+            return false;
+        }
+        let found: Vec<(String, String)> = self.active_blocks()
+            .iter()
+            .filter(|block| !self.config.active_hosts.contains_key(&block.host.to_ascii_lowercase()))
+            .filter_map(|block| Some((block.host.clone(), self.account_for_active_block(block)?.name.clone())))
+            .collect();
+        for (host, account) in found {
+            self.set_active_host(&host, &account);
+        }
+        true
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub(crate) enum SyncSource {
+    /// Record in gam_config.json what the active blocks in ~/.ssh/config use
+    Ssh,
+    /// Rewrite the active blocks in ~/.ssh/config from gam_config.json
+    Config,
+}
+
+// What ~/.ssh/config says about one host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ActiveBlockState {
+    Missing,
+    // The block offers no key of a gam account
+    ForeignKey,
+    Account(String),
+}
+
+// A host where gam_config.json and ~/.ssh/config disagree on the active account
+pub(crate) struct ActiveDrift {
+    pub(crate) host: String,
+    pub(crate) recorded: Option<String>,
+    pub(crate) block: ActiveBlockState,
+}
+
+impl SshAccount {
+    // Hosts whose active block in ~/.ssh/config offers this account's key
+    pub(crate) fn active_hosts_in(&self, in_ssh_config: &BTreeMap<String, ActiveBlockState>) -> Vec<&str> {
+        self.hosts()
+            .into_iter()
+            .filter(|host| {
+                matches!(in_ssh_config.get(&host.to_ascii_lowercase()), Some(ActiveBlockState::Account(name)) if *name == self.name)
+            })
+            .collect()
+    }
+}
+
+impl ActiveDrift {
+    fn describe(&self) -> String {
+        match (&self.recorded, &self.block) {
+            (Some(recorded), ActiveBlockState::Missing) => format!(
+                "{}: gam_config.json has '{}' active but ~/.ssh/config has no active block",
+                self.host, recorded
+            ),
+            (Some(recorded), ActiveBlockState::ForeignKey) => format!(
+                "{}: gam_config.json has '{}' active but the active block offers no gam account's key",
+                self.host, recorded
+            ),
+            (Some(recorded), ActiveBlockState::Account(found)) => format!(
+                "{}: gam_config.json has '{}' active but the active block uses the key of '{}'",
+                self.host, recorded, found
+            ),
+            (None, ActiveBlockState::ForeignKey) => format!(
+                "{}: the active block offers no gam account's key",
+                self.host
+            ),
+            (None, ActiveBlockState::Account(found)) => format!(
+                "{}: the active block uses the key of '{}' but gam_config.json has no active account",
+                self.host, found
+            ),
+            (None, ActiveBlockState::Missing) => format!("{}: nothing active", self.host),
+        }
+    }
+}
+
+impl SshManager {
+    // Account each active block in ~/.ssh/config actually offers, by lowercased host
+    pub(crate) fn active_in_ssh_config(&self) -> BTreeMap<String, ActiveBlockState> {
+        self.active_blocks()
+            .iter()
+            .map(|block| {
+                let state = match self.account_for_active_block(block) {
+                    Some(account) => ActiveBlockState::Account(account.name.clone()),
+                    None => ActiveBlockState::ForeignKey,
+                };
+                (block.host.to_ascii_lowercase(), state)
+            })
+            .collect()
+    }
+
+    pub(crate) fn active_drift(&self, in_ssh_config: &BTreeMap<String, ActiveBlockState>) -> Vec<ActiveDrift> {
+        let hosts: BTreeSet<&String> = in_ssh_config.keys().chain(self.config.active_hosts.keys()).collect();
+        hosts.into_iter()
+            .filter_map(|host| {
+                let recorded = self.config.active_hosts.get(host).cloned();
+                let block = in_ssh_config.get(host).cloned().unwrap_or(ActiveBlockState::Missing);
+                let agrees = matches!((&recorded, &block), (Some(recorded), ActiveBlockState::Account(found)) if recorded == found);
+                (!agrees).then(|| ActiveDrift { host: host.clone(), recorded, block })
+            })
+            .collect()
+    }
+
+    // Print where gam_config.json and ~/.ssh/config disagree; used by `gam status`
+    pub(crate) fn report_active_drift(&self, in_ssh_config: &BTreeMap<String, ActiveBlockState>) {
+        let drift = self.active_drift(in_ssh_config);
+        if drift.is_empty() {
+            return;
+        }
+        println!("\n⚠️  ~/.ssh/config and gam_config.json disagree on the active account:");
+        for entry in &drift {
+            println!("   {}", entry.describe());
+        }
+        println!("ℹ️  Run 'gam sync --from ssh' to keep ~/.ssh/config, or 'gam sync --from config' to rewrite it");
+    }
+
+    pub(crate) fn sync_active(&mut self, from: SyncSource) -> Result<()> {
+        let drift = self.active_drift(&self.active_in_ssh_config());
+        if drift.is_empty() {
+            println!("✅ gam_config.json and ~/.ssh/config already agree on the active accounts");
+            return Ok(());
+        }
+
+        let mut synced = 0;
+        match from {
+            SyncSource::Ssh => {
+                for entry in &drift {
+                    match &entry.block {
+                        ActiveBlockState::Account(found) => {
+                            let serves = self.config.accounts.get(found).is_some_and(|account| account.serves_host(&entry.host));
+                            if !serves {
+                                println!("⚠️  {}: '{}' does not serve this host; fix the block by hand", entry.host, found);
+                                continue;
+                            }
+                            self.set_active_host(&entry.host, found);
+                            println!("✅ {}: '{}' recorded as active", entry.host, found);
+                        }
+                        ActiveBlockState::ForeignKey => {
+                            println!(
+                                "⚠️  {}: the active block offers no gam account's key; edit it by hand or run 'gam sync --from config'",
+                                entry.host
+                            );
+                            // Nothing to record, but a stale account must not stay active
+                            if entry.recorded.is_none() {
+                                continue;
+                            }
+                            self.unset_active_host(&entry.host);
+                            println!("✅ {}: no account recorded as active", entry.host);
+                        }
+                        ActiveBlockState::Missing => {
+                            self.unset_active_host(&entry.host);
+                            println!("✅ {}: no account recorded as active", entry.host);
+                        }
+                    }
+                    synced += 1;
+                }
+                self.save_config().context("Failed to save configuration")?;
+            }
+            SyncSource::Config => {
+                for entry in &drift {
+                    match entry.recorded.as_ref().and_then(|name| self.config.accounts.get(name)) {
+                        Some(account) => self.upsert_active_mapping_for_host(account, &entry.host)?,
+                        None => {
+                            if let Some(recorded) = &entry.recorded {
+                                println!(
+                                    "⚠️  {}: recorded account '{}' no longer exists; removing the active block",
+                                    entry.host, recorded
+                                );
+                                self.unset_active_host(&entry.host);
+                            }
+                            self.clear_active_mapping_for_host(&entry.host)?;
+                        }
+                    }
+                    synced += 1;
+                }
+                self.save_config().context("Failed to save configuration")?;
+            }
+        }
+        println!("✅ Synced {} host(s)", synced);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    fn account(name: &str, key_file: &str, host: &str) -> SshAccount {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "email": format!("{}@example.com", name),
+            "key_file": key_file,
+            "host": host,
+            "description": null,
+        }))
+        .unwrap()
+    }
+
+    // Throwaway home directory, removed when the test ends
+    struct TempHome(PathBuf);
+
+    impl Drop for TempHome {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    // Manager over a throwaway ~/.ssh holding `ssh_config`; keep the guard alive for the whole test
+    fn manager(test: &str, ssh_config: &str, accounts: &[SshAccount]) -> (TempHome, SshManager) {
+        let home = TempHome(std::env::temp_dir().join(format!("gam-active-{}-{}", std::process::id(), test)));
+        let ssh_dir = home.0.join(".ssh");
+        fs::create_dir_all(&ssh_dir).unwrap();
+        fs::write(ssh_dir.join("config"), ssh_config).unwrap();
+        let mut config = Config::default();
+        for account in accounts {
+            config.accounts.insert(account.name.clone(), account.clone());
+        }
+        (home, SshManager { config_path: ssh_dir.join("gam_config.json"), ssh_dir, config })
+    }
+
+    #[test]
+    fn parses_active_blocks_in_every_spelling() {
+        let content = "\
+Host personal
+    IdentityFile ~/.ssh/id_other
+# gam ACTIVE START [github.com]
+Host github.com
+    identityfile=~/.ssh/id_work
+    IdentityFile = \"/home/me/.ssh/id work\"
+# gam ACTIVE END [github.com]
+# ssh-manager ACTIVE START [gitlab.com]
+Host gitlab.com
+    IdentityFile /home/me/.ssh/id_gl
+# ssh-manager ACTIVE END [gitlab.com]
+# gam ACTIVE START [git.corp.com]
+Host git.corp.com
+    IdentityFile\t~/.ssh/id_corp
+";
+        let blocks = parse_active_blocks(content);
+        let parsed: Vec<(&str, Vec<&str>)> = blocks.iter()
+            .map(|block| (block.host.as_str(), block.identity_files.iter().map(|f| f.as_str()).collect()))
+            .collect();
+        assert_eq!(parsed, vec![
+            ("github.com", vec!["~/.ssh/id_work", "/home/me/.ssh/id work"]),
+            ("gitlab.com", vec!["/home/me/.ssh/id_gl"]),
+            ("git.corp.com", vec!["~/.ssh/id_corp"]),
+        ]);
+    }
+
+    #[test]
+    fn unterminated_block_ends_at_the_next_marker() {
+        let content = "\
+# gam ACTIVE START [github.com]
+Host github.com
+    IdentityFile ~/.ssh/id_work
+# gam ACTIVE START [gitlab.com]
+Host gitlab.com
+    IdentityFile ~/.ssh/id_gl
+# gam ACTIVE END [gitlab.com]
+";
+        let blocks = parse_active_blocks(content);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].identity_files, vec!["~/.ssh/id_work"]);
+        assert_eq!(blocks[1].identity_files, vec!["~/.ssh/id_gl"]);
+    }
+
+    #[test]
+    fn resolves_identity_files_to_accounts() {
+        let work = account("work", "id_work", "github.com");
+        let (_home, manager) = manager("resolve", "", std::slice::from_ref(&work));
+        let absolute = manager.ssh_dir.join("id_work").display().to_string();
+        assert!(manager.owns_identity_file(&work, "~/.ssh/id_work"));
+        assert!(manager.owns_identity_file(&work, &absolute));
+        assert!(manager.owns_identity_file(&work, "id_work"));
+        assert!(!manager.owns_identity_file(&work, "~/.ssh/id_work.pub"));
+        assert!(!manager.owns_identity_file(&work, "~/elsewhere/id_work"));
+    }
+
+    #[test]
+    fn migrates_current_account_from_the_active_blocks() {
+        let ssh_config = "\
+# gam ACTIVE START [github.com]
+Host github.com
+    IdentityFile ~/.ssh/id_home
+# gam ACTIVE END [github.com]
+# ssh-manager ACTIVE START [GitLab.com]
+Host GitLab.com
+    IdentityFile=\"~/.ssh/id_gl\"
+# ssh-manager ACTIVE END [GitLab.com]
+# gam ACTIVE START [example.org]
+Host example.org
+    IdentityFile ~/.ssh/id_unknown
+# gam ACTIVE END [example.org]
+";
+        let accounts = [
+            account("work", "id_work", "github.com"),
+            account("home", "id_home", "github.com"),
+            account("gitlab", "id_gl", "gitlab.com"),
+        ];
+        let (_home, mut manager) = manager("migrate", ssh_config, &accounts);
+        // The stale JSON claimed `work`; the block really offers `home`'s key
+        manager.config.current_account = Some("work".to_string());
+
+        assert!(manager.migrate_active_hosts());
+        assert_eq!(manager.config.current_account, None);
+        let mapped: Vec<(&str, &str)> = manager.config.active_hosts.iter()
+            .map(|(host, name)| (host.as_str(), name.as_str()))
+            .collect();
+        assert_eq!(mapped, vec![("github.com", "home"), ("gitlab.com", "gitlab")]);
+
+        // Configs written since only carry `active_hosts`
+        assert!(!manager.migrate_active_hosts());
+    }
+
+    #[test]
+    fn migration_keeps_hosts_already_mapped() {
+        let ssh_config = "\
+# gam ACTIVE START [github.com]
+Host github.com
+    IdentityFile ~/.ssh/id_home
+# gam ACTIVE END [github.com]
+";
+        let accounts = [account("work", "id_work", "github.com"), account("home", "id_home", "github.com")];
+        let (_home, mut manager) = manager("migrate-mapped", ssh_config, &accounts);
+        manager.config.current_account = Some("home".to_string());
+        manager.config.active_hosts.insert("github.com".to_string(), "work".to_string());
+
+        assert!(manager.migrate_active_hosts());
+        assert_eq!(manager.config.active_hosts.get("github.com").map(|name| name.as_str()), Some("work"));
+    }
+
+    #[test]
+    fn drift_lists_only_hosts_that_disagree() {
+        let accounts = [
+            account("work", "id_work", "github.com"),
+            account("home", "id_home", "github.com"),
+            account("gitlab", "id_gl", "gitlab.com"),
+            account("corp", "id_corp", "git.corp.com"),
+        ];
+        let (_home, mut manager) = manager("drift", "", &accounts);
+        for (host, name) in [
+            ("github.com", "work"),
+            ("gitlab.com", "gitlab"),
+            ("git.corp.com", "corp"),
+            ("bitbucket.org", "gone"),
+            ("example.org", "home"),
+        ] {
+            manager.config.active_hosts.insert(host.to_string(), name.to_string());
+        }
+        let in_ssh_config = BTreeMap::from([
+            // Agrees with gam_config.json
+            ("github.com".to_string(), ActiveBlockState::Account("work".to_string())),
+            ("gitlab.com".to_string(), ActiveBlockState::ForeignKey),
+            ("git.corp.com".to_string(), ActiveBlockState::Account("home".to_string())),
+            ("codeberg.org".to_string(), ActiveBlockState::Account("home".to_string())),
+            ("sr.ht".to_string(), ActiveBlockState::ForeignKey),
+        ]);
+
+        let drift = manager.active_drift(&in_ssh_config);
+        let found: Vec<(&str, Option<&str>, &ActiveBlockState)> = drift.iter()
+            .map(|entry| (entry.host.as_str(), entry.recorded.as_deref(), &entry.block))
+            .collect();
+        assert_eq!(found, vec![
+            ("bitbucket.org", Some("gone"), &ActiveBlockState::Missing),
+            ("codeberg.org", None, &ActiveBlockState::Account("home".to_string())),
+            ("example.org", Some("home"), &ActiveBlockState::Missing),
+            ("git.corp.com", Some("corp"), &ActiveBlockState::Account("home".to_string())),
+            ("gitlab.com", Some("gitlab"), &ActiveBlockState::ForeignKey),
+            ("sr.ht", None, &ActiveBlockState::ForeignKey),
+        ]);
+    }
+
+    #[test]
+    fn no_drift_when_both_sides_agree() {
+        let ssh_config = "\
+# gam ACTIVE START [GitHub.com]
+Host GitHub.com
+    IdentityFile ~/.ssh/id_work
+# gam ACTIVE END [GitHub.com]
+";
+        let (_home, mut manager) = manager("agree", ssh_config, &[account("work", "id_work", "github.com")]);
+        manager.config.active_hosts.insert("github.com".to_string(), "work".to_string());
+        assert!(manager.active_drift(&manager.active_in_ssh_config()).is_empty());
+    }
+
+    #[test]
+    fn sync_from_ssh_forgets_accounts_whose_block_is_foreign() {
+        let ssh_config = "\
+# gam ACTIVE START [github.com]
+Host github.com
+    IdentityFile ~/.ssh/id_someone_else
+# gam ACTIVE END [github.com]
+# gam ACTIVE START [gitlab.com]
+Host gitlab.com
+    IdentityFile ~/.ssh/id_gl
+# gam ACTIVE END [gitlab.com]
+";
+        let accounts = [account("work", "id_work", "github.com"), account("gitlab", "id_gl", "gitlab.com")];
+        let (_home, mut manager) = manager("sync-ssh", ssh_config, &accounts);
+        manager.config.active_hosts.insert("github.com".to_string(), "work".to_string());
+
+        manager.sync_active(SyncSource::Ssh).unwrap();
+        let mapped: Vec<(&str, &str)> = manager.config.active_hosts.iter()
+            .map(|(host, name)| (host.as_str(), name.as_str()))
+            .collect();
+        assert_eq!(mapped, vec![("gitlab.com", "gitlab")]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+
+use crate::vault::{ASKPASS_ENV, ASKPASS_NEW_SECRET_ENV, ASKPASS_SECRET_ENV};
+use crate::keys::{KeyRole, PRIMARY_KEY_LABEL};
+use crate::{SshAccount, SshManager};
+
+impl SshManager {
+    // SHA256 fingerprint of a key file, read from the .pub when available
+    pub(crate) fn key_fingerprint(key_path: &Path) -> Option<String> {
+        let pub_path = PathBuf::from(format!("{}.pub", key_path.display()));
+        let target = if pub_path.exists() { pub_path } else { key_path.to_path_buf() };
+        let output = Command::new("ssh-keygen")
+            .arg("-lf")
+            .arg(&target)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .nth(1)
+            .map(|fp| fp.to_string())
+    }
+
+    // Fingerprints currently held by ssh-agent; errors when no agent is reachable
+    pub(crate) fn agent_fingerprints() -> Result<Vec<String>> {
+        let output = Command::new("ssh-add")
+            .arg("-l")
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run ssh-add")?;
+
+        // ssh-add -l exits 1 when the agent has no identities and 2 when there is no agent
+        match output.status.code() {
+            Some(0) => Ok(String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.split_whitespace().nth(1))
+                .map(|fp| fp.to_string())
+                .collect()),
+            Some(1) => Ok(Vec::new()),
+            _ => Err(anyhow::anyhow!("Could not connect to ssh-agent (is SSH_AUTH_SOCK set?)")),
+        }
+    }
+
+    // Whether `passphrase` decrypts the private key
+    pub(crate) fn passphrase_unlocks(key_path: &Path, passphrase: &str) -> bool {
+        let mut cmd = Command::new("ssh-keygen");
+        cmd.arg("-y").arg("-f").arg(key_path);
+        if Self::feed_passphrase(&mut cmd, passphrase).is_err() {
+            return false;
+        }
+        cmd.stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    // Answer ssh's passphrase prompts with `gam askpass`; secrets go through the environment,
+    // which unlike the command line other users cannot read
+    fn use_gam_askpass(cmd: &mut Command) -> Result<()> {
+        let exe = env::current_exe().context("Failed to locate the gam executable")?;
+        cmd.env("SSH_ASKPASS", exe)
+            .env("SSH_ASKPASS_REQUIRE", "force")
+            .env(ASKPASS_ENV, "1")
+            .stdin(Stdio::null());
+        // OpenSSH before 8.4 ignores SSH_ASKPASS_REQUIRE and only uses askpass with a DISPLAY
+        if env::var_os("DISPLAY").is_none() {
+            cmd.env("DISPLAY", ":0");
+        }
+        Ok(())
+    }
+
+    // Make ssh-add (or ssh-keygen) read the passphrase from `gam askpass` instead of the terminal
+    pub(crate) fn feed_passphrase(cmd: &mut Command, passphrase: &str) -> Result<()> {
+        Self::use_gam_askpass(cmd)?;
+        cmd.env(ASKPASS_SECRET_ENV, passphrase);
+        Ok(())
+    }
+
+    // Passphrase for a key ssh-keygen is creating or rewrapping
+    pub(crate) fn feed_new_passphrase(cmd: &mut Command, passphrase: &str) -> Result<()> {
+        if passphrase.is_empty() {
+            cmd.arg("-N").arg("");
+            return Ok(());
+        }
+        Self::use_gam_askpass(cmd)?;
+        cmd.env(ASKPASS_NEW_SECRET_ENV, passphrase);
+        Ok(())
+    }
+
+    fn account_for_agent(&self, account_name: &str) -> Result<&SshAccount> {
+        self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))
+    }
+
+    // Keys worth holding in the agent: authentication and signing keys, not per-repository deploy keys
+    fn agent_keys(account: &SshAccount) -> Vec<(&str, &str, KeyRole)> {
+        Self::account_keys(account)
+            .into_iter()
+            .filter(|(_, _, role)| *role != KeyRole::Deploy)
+            .collect()
+    }
+
+    pub(crate) fn agent_status(&self) -> Result<()> {
+        let loaded = Self::agent_fingerprints()?;
+
+        if self.config.accounts.is_empty() {
+            println!("📭 No accounts found. Use 'gam add' to create one.");
+        } else {
+            println!("\n🔑 ssh-agent keys:\n");
+        }
+
+        let mut gam_loaded = 0;
+        for (name, account) in &self.config.accounts {
+            for (label, file, role) in Self::agent_keys(account) {
+                let key_path = self.ssh_dir.join(file);
+                let key = if label == PRIMARY_KEY_LABEL {
+                    name.to_string()
+                } else {
+                    format!("{} {} [{}]", name, label, role.as_str())
+                };
+                match Self::key_fingerprint(&key_path) {
+                    Some(fingerprint) if loaded.contains(&fingerprint) => {
+                        gam_loaded += 1;
+                        println!("  🟢 {} ({}) loaded", key, fingerprint);
+                    }
+                    Some(fingerprint) => println!("  ⚪ {} ({}) not loaded", key, fingerprint),
+                    None => println!("  ⚠️  {} key file {} not readable", key, key_path.display()),
+                }
+            }
+        }
+
+        let others = loaded.len().saturating_sub(gam_loaded);
+        if others > 0 {
+            println!("\nℹ️  {} other key(s) not managed by gam are loaded", others);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn agent_load(&self, account_name: &str, lifetime: Option<&str>) -> Result<()> {
+        let account = self.account_for_agent(account_name)?;
+        let agent_keys = Self::agent_keys(account);
+
+        // The stored passphrase belongs to the keys gam generated; keys registered with --file keep
+        // their own, so ssh-add asks for it. Looked up once: opening the vault prompts
+        let generated = Self::generated_key_files(account);
+        let passphrase = if agent_keys.iter().any(|(_, file, _)| generated.contains(file)) {
+            self.stored_passphrase(&account.name)?
+        } else {
+            None
+        };
+
+        let mut loaded = 0;
+        for (label, file, _) in agent_keys {
+            let key_path = self.ssh_dir.join(file);
+            let mut add_cmd = Command::new("ssh-add");
+            if let Some(passphrase) = passphrase.as_deref().filter(|_| generated.contains(&file)) {
+                Self::feed_passphrase(&mut add_cmd, passphrase)?;
+            }
+            if cfg!(target_os = "macos") {
+                add_cmd.arg("--apple-use-keychain");
+            }
+            if let Some(lifetime) = lifetime {
+                add_cmd.arg("-t").arg(lifetime);
+            }
+            let status = add_cmd
+                .arg(&key_path)
+                .status()
+                .context("Failed to run ssh-add")?;
+            if status.success() {
+                loaded += 1;
+            } else if label != PRIMARY_KEY_LABEL {
+                println!("⚠️  ssh-add failed to load key '{}' of '{}'", label, account_name);
+            } else {
+                return Err(anyhow::anyhow!("ssh-add failed to load the key for '{}'", account_name));
+            }
+        }
+
+        match lifetime {
+            Some(lifetime) => println!("✅ {} key(s) for '{}' loaded into ssh-agent for {}", loaded, account_name, lifetime),
+            None => println!("✅ {} key(s) for '{}' loaded into ssh-agent", loaded, account_name),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn agent_unload(&self, account_name: &str) -> Result<()> {
+        let account = self.account_for_agent(account_name)?;
+
+        let mut removed = 0;
+        for (_, file, _) in Self::account_keys(account) {
+            let status = Command::new("ssh-add")
+                .arg("-d")
+                .arg(self.ssh_dir.join(file))
+                .stderr(Stdio::null())
+                .status()
+                .context("Failed to run ssh-add")?;
+            if status.success() {
+                removed += 1;
+            }
+        }
+
+        if removed > 0 {
+            println!("✅ {} key(s) for '{}' removed from ssh-agent", removed, account_name);
+        } else {
+            println!("ℹ️  No key for '{}' was loaded in ssh-agent", account_name);
+        }
+        Ok(())
+    }
+
+    // Leave only one account's key in the agent to avoid "Too many authentication failures"
+    pub(crate) fn agent_only(&self, account_name: Option<&str>, lifetime: Option<&str>) -> Result<()> {
+        let account_name = match account_name {
+            Some(name) => name.to_string(),
+            None => self.effective_account_for_repo(None)
+                .map(|name| name.to_string())
+                .context("No active account for this repository. Use 'gam switch' or pass an account name")?,
+        };
+        self.account_for_agent(&account_name)?;
+
+        let status = Command::new("ssh-add")
+            .arg("-D")
+            .stderr(Stdio::null())
+            .status()
+            .context("Failed to run ssh-add")?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("Could not clear ssh-agent identities"));
+        }
+        println!("ℹ️  All identities removed from ssh-agent");
+
+        self.agent_load(&account_name, lifetime)
+    }
+}
@@ -0,0 +1,291 @@
+use std::fs;
+use anyhow::{Context, Result};
+use inquire::Text;
+
+use crate::{SshAccount, SshManager};
+
+// Matches the aliases gam has always generated, e.g. "github-work"
+pub(crate) const DEFAULT_ALIAS_TEMPLATE: &str = "{host_prefix}-{name}";
+const TEMPLATE_PLACEHOLDERS: &[&str] = &["{host}", "{host_prefix}", "{name}"];
+
+fn render_alias_template(template: &str, name: &str, host: &str) -> String {
+    let host_prefix = host.split('.').next().unwrap_or(host);
+    template
+        .replace("{host_prefix}", host_prefix)
+        .replace("{host}", host)
+        .replace("{name}", &name.replace(' ', "-"))
+}
+
+// Extra hosts often share a prefix with the primary one (github.com, github.example.com),
+// so their aliases always carry the full host
+fn extra_host_template(template: &str) -> String {
+    let template = template.replace("{host_prefix}", "{host}");
+    if template.contains("{host}") {
+        template
+    } else {
+        format!("{{host}}-{}", template)
+    }
+}
+
+fn check_alias_syntax(alias: &str) -> Result<()> {
+    if alias.is_empty() {
+        return Err(anyhow::anyhow!("Alias cannot be empty"));
+    }
+    // ssh_config treats these as pattern syntax or separators in `Host` lines
+    if let Some(c) = alias.chars().find(|c| c.is_whitespace() || matches!(c, '*' | '?' | '!' | ',' | '#' | '"')) {
+        return Err(anyhow::anyhow!("Alias '{}' cannot contain '{}'", alias, c));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_alias_template(template: &str) -> Result<()> {
+    if !template.contains("{name}") {
+        return Err(anyhow::anyhow!("Template '{}' must contain {{name}} so aliases stay unique", template));
+    }
+    let mut rest = template.to_string();
+    for placeholder in TEMPLATE_PLACEHOLDERS {
+        rest = rest.replace(placeholder, "");
+    }
+    if rest.contains('{') || rest.contains('}') {
+        return Err(anyhow::anyhow!(
+            "Unknown placeholder in '{}'; use {}",
+            template,
+            TEMPLATE_PLACEHOLDERS.join(", ")
+        ));
+    }
+    if !rest.is_empty() {
+        check_alias_syntax(&rest)?;
+    }
+    Ok(())
+}
+
+impl SshManager {
+    fn alias_template(&self) -> &str {
+        self.config.alias_template.as_deref().unwrap_or(DEFAULT_ALIAS_TEMPLATE)
+    }
+
+    // The account's explicit alias, else the configured template, e.g. "github-work"
+    pub(crate) fn alias_for(&self, account: &SshAccount) -> String {
+        account.alias.clone()
+            .unwrap_or_else(|| self.templated_alias(&account.name, &account.host))
+    }
+
+    // Alias for one of the account's hosts; the explicit alias names the primary host's block
+    pub(crate) fn alias_for_host(&self, account: &SshAccount, host: &str) -> String {
+        if host.eq_ignore_ascii_case(&account.host) {
+            self.alias_for(account)
+        } else if let Some(alias) = account.host_aliases.get(&host.to_ascii_lowercase()) {
+            alias.clone()
+        } else {
+            render_alias_template(&extra_host_template(self.alias_template()), &account.name, host)
+        }
+    }
+
+    // (host, alias) for every host of the account
+    pub(crate) fn aliases_of(&self, account: &SshAccount) -> Vec<(String, String)> {
+        account.hosts()
+            .into_iter()
+            .map(|host| (host.to_string(), self.alias_for_host(account, host)))
+            .collect()
+    }
+
+    // (host, old alias, new alias) for each host whose alias differs between two versions of an account
+    pub(crate) fn changed_aliases(&self, old: &SshAccount, new: &SshAccount) -> Vec<(String, String, String)> {
+        self.aliases_of(old)
+            .into_iter()
+            .zip(self.aliases_of(new))
+            .filter(|((_, old_alias), (_, new_alias))| old_alias != new_alias)
+            .map(|((_, old_alias), (host, new_alias))| (host, old_alias, new_alias))
+            .collect()
+    }
+
+    // Account owning a current alias
+    pub(crate) fn account_for_alias(&self, alias: &str) -> Option<&SshAccount> {
+        self.config.accounts.values()
+            .find(|account| self.aliases_of(account).iter().any(|(_, a)| a == alias))
+    }
+
+    // Alias the template gives an account without an explicit one
+    pub(crate) fn templated_alias(&self, name: &str, host: &str) -> String {
+        render_alias_template(self.alias_template(), name, host)
+    }
+
+    // Prompt for an alias until it is valid; None keeps following the template
+    pub(crate) fn prompt_alias(&self, name: &str, host: &str, current: Option<&str>) -> Result<Option<String>> {
+        let templated = self.templated_alias(name, host);
+        loop {
+            let input = Text::new("SSH alias:")
+                .with_default(current.unwrap_or(&templated))
+                .prompt()
+                .context("Failed to get SSH alias")?;
+            let input = input.trim().to_string();
+            match self.check_alias(&input, name) {
+                Ok(()) => return Ok(Some(input).filter(|alias| *alias != templated)),
+                Err(e) => println!("❌ {}", e),
+            }
+        }
+    }
+
+    // `Host` names in ~/.ssh/config that gam did not write
+    fn foreign_ssh_hosts(&self) -> Vec<String> {
+        let content = fs::read_to_string(self.ssh_dir.join("config")).unwrap_or_default();
+        let gam_aliases: Vec<String> = self.config.accounts.values()
+            .flat_map(|account| self.aliases_of(account).into_iter().map(|(_, alias)| alias))
+            .chain(self.config.deprecated_aliases.iter().map(|entry| entry.alias.clone()))
+            .chain(self.config.deploy_keys.values().map(|deploy_key| deploy_key.alias.clone()))
+            .collect();
+
+        let mut hosts = Vec::new();
+        let mut in_gam_block = false;
+        for line in content.lines() {
+            let text = line.trim();
+            if text.starts_with("# gam ACTIVE START")
+                || text.starts_with("# ssh-manager ACTIVE START")
+                || text.starts_with("# gam DEPRECATED START")
+                || text.starts_with("# gam DEPLOY START")
+            {
+                in_gam_block = true;
+            } else if text.starts_with("# gam ACTIVE END")
+                || text.starts_with("# ssh-manager ACTIVE END")
+                || text.starts_with("# gam DEPRECATED END")
+                || text.starts_with("# gam DEPLOY END")
+            {
+                in_gam_block = false;
+            } else if !in_gam_block {
+                let Some((keyword, names)) = text.split_once(char::is_whitespace) else {
+                    continue;
+                };
+                if keyword.eq_ignore_ascii_case("Host") {
+                    hosts.extend(
+                        names.split_whitespace()
+                            .filter(|name| !gam_aliases.iter().any(|alias| alias == name))
+                            .map(|name| name.to_string()),
+                    );
+                }
+            }
+        }
+        hosts
+    }
+
+    // Reject aliases that would shadow another account, a real host or a hand-written Host entry
+    pub(crate) fn check_alias(&self, alias: &str, account_name: &str) -> Result<()> {
+        check_alias_syntax(alias)?;
+
+        for other in self.config.accounts.values().filter(|other| other.name != account_name) {
+            if self.aliases_of(other).iter().any(|(_, other_alias)| other_alias == alias) {
+                return Err(anyhow::anyhow!("Alias '{}' is already used by account '{}'", alias, other.name));
+            }
+        }
+        if let Some(account) = self.config.accounts.values().find(|account| account.serves_host(alias)) {
+            return Err(anyhow::anyhow!(
+                "Alias '{}' is the host of account '{}'; it would clash with its active mapping",
+                alias,
+                account.name
+            ));
+        }
+        if let Some(entry) = self.config.deprecated_aliases.iter().find(|entry| entry.alias == alias) {
+            if entry.account != account_name {
+                return Err(anyhow::anyhow!(
+                    "Alias '{}' is still reserved for '{}' after a rename; run 'gam doctor' once it expires",
+                    alias,
+                    entry.account
+                ));
+            }
+        }
+        if let Some((id, _)) = self.config.deploy_keys.iter().find(|(_, deploy_key)| deploy_key.alias == alias) {
+            return Err(anyhow::anyhow!("Alias '{}' is used by the deploy key for {}", alias, id));
+        }
+        if self.foreign_ssh_hosts().iter().any(|host| host == alias) {
+            return Err(anyhow::anyhow!("~/.ssh/config already has a 'Host {}' entry not managed by gam", alias));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn alias_set(&mut self, account_name: &str, alias: Option<&str>) -> Result<()> {
+        let old = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?
+            .clone();
+        let mut new = old.clone();
+        new.alias = alias.map(|alias| alias.trim().to_string())
+            .filter(|alias| !alias.is_empty() && *alias != self.templated_alias(&old.name, &old.host));
+
+        if !self.check_account_edit(&old, &new)? {
+            println!("ℹ️  '{}' already uses alias {}", account_name, self.alias_for(&old));
+            return Ok(());
+        }
+        let old_alias = self.alias_for(&old);
+        self.apply_account_edit(&old, new.clone())?;
+        println!("✅ Alias for '{}' changed from {} to {}", account_name, old_alias, self.alias_for(&new));
+        println!("ℹ️  Update remotes that still use {}", old_alias);
+        Ok(())
+    }
+
+    // Change the template for accounts without an explicit alias; existing aliases are pinned
+    pub(crate) fn alias_template_set(&mut self, template: Option<&str>) -> Result<()> {
+        let Some(template) = template else {
+            println!("{}", self.alias_template());
+            return Ok(());
+        };
+        check_alias_template(template)?;
+
+        // Pin every current alias so the accounts look unchanged under the new template
+        let originals: Vec<SshAccount> = self.config.accounts.values().cloned().collect();
+        let pinned = originals.iter().filter(|account| account.alias.is_none()).count();
+        let pins: Vec<SshAccount> = originals.iter()
+            .map(|account| {
+                let mut pin = account.clone();
+                pin.alias = Some(self.alias_for(account));
+                pin.host_aliases = self.aliases_of(account)
+                    .into_iter()
+                    .filter(|(host, _)| !host.eq_ignore_ascii_case(&account.host))
+                    .map(|(host, alias)| (host.to_ascii_lowercase(), alias))
+                    .collect();
+                pin
+            })
+            .collect();
+        let old_template = self.config.alias_template.take();
+        self.config.alias_template = if template == DEFAULT_ALIAS_TEMPLATE { None } else { Some(template.to_string()) };
+        for pin in &pins {
+            self.config.accounts.insert(pin.name.clone(), pin.clone());
+        }
+
+        // Extra hosts follow the template, so releasing their pins is an alias edit like any other
+        let edits: Vec<(SshAccount, SshAccount)> = pins.iter()
+            .filter(|pin| !pin.host_aliases.is_empty())
+            .map(|pin| {
+                let mut released = pin.clone();
+                released.host_aliases.clear();
+                (pin.clone(), released)
+            })
+            .collect();
+        for (pin, released) in &edits {
+            if let Err(e) = self.check_account_edit(pin, released) {
+                self.config.alias_template = old_template;
+                for account in originals {
+                    self.config.accounts.insert(account.name.clone(), account);
+                }
+                return Err(e);
+            }
+        }
+        self.save_config().context("Failed to save configuration")?;
+
+        for (pin, released) in edits {
+            let changed = self.changed_aliases(&pin, &released);
+            if changed.is_empty() {
+                self.config.accounts.insert(released.name.clone(), released);
+                self.save_config().context("Failed to save configuration")?;
+                continue;
+            }
+            self.apply_account_edit(&pin, released)?;
+            for (host, _, new_alias) in changed {
+                println!("⚠️  Alias for {} on '{}' is now {}", host, pin.name, new_alias);
+            }
+        }
+
+        println!("✅ New accounts will use aliases like '{}'", template);
+        if pinned > 0 {
+            println!("ℹ️  {} existing account(s) keep their current alias; change one with 'gam alias set'", pinned);
+        }
+        Ok(())
+    }
+}
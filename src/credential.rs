@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+use std::process::Command;
+use anyhow::{Context, Result};
+use inquire::{Confirm, Password, Select, Text};
+
+use crate::validate::{validate_account_name, validate_description, validate_email, validate_host};
+use crate::vault::{token_key, Vault};
+use crate::{HttpsAccount, SshManager};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub(crate) enum CredentialAction {
+    Get,
+    Store,
+    Erase,
+}
+
+// git credential protocol: "key=value" lines terminated by a blank line or EOF
+fn read_credential_request() -> Result<BTreeMap<String, String>> {
+    let mut attributes = BTreeMap::new();
+    for line in io::stdin().lock().lines() {
+        let line = line.context("Failed to read credential request")?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            attributes.insert(key.to_string(), value.to_string());
+        }
+    }
+    Ok(attributes)
+}
+
+impl SshManager {
+    pub(crate) fn add_https_account(&mut self) -> Result<()> {
+        println!("\n🌐 Adding a new HTTPS account\n");
+
+        let name = loop {
+            let input = Text::new("Account name (e.g., 'work-https'):")
+                .prompt()
+                .context("Failed to get account name")?;
+
+            match validate_account_name(input.trim()) {
+                Ok(()) => break input.trim().to_string(),
+                Err(e) => println!("❌ {}", e),
+            }
+        };
+
+        if self.config.accounts.contains_key(&name) || self.config.https_accounts.contains_key(&name) {
+            println!("❌ Account '{}' already exists!", name);
+            return Ok(());
+        }
+
+        let email = loop {
+            let input = Text::new("Email address:")
+                .prompt()
+                .context("Failed to get email")?;
+
+            match validate_email(input.trim()) {
+                Ok(()) => break input.trim().to_string(),
+                Err(e) => println!("❌ {}", e),
+            }
+        };
+
+        let host = Select::new(
+            "Select the host type:",
+            vec!["github.com", "gitlab.com", "bitbucket.org", "Custom"],
+        )
+        .prompt()
+        .context("Failed to get host selection")?;
+
+        let host = if host == "Custom" {
+            loop {
+                let input = Text::new("Enter custom host (e.g. 'git.corp.example' or 'git.corp.example:8443'):")
+                    .prompt()
+                    .context("Failed to get custom host")?;
+
+                match validate_host(input.trim()) {
+                    Ok(()) => break input.trim().to_string(),
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+        } else {
+            host.to_string()
+        };
+
+        let username = Text::new("Username for HTTPS:")
+            .prompt()
+            .context("Failed to get username")?;
+
+        let token = Password::new("Personal access token:")
+            .without_confirmation()
+            .prompt()
+            .context("Failed to get token")?;
+
+        let description = loop {
+            let input = Text::new("Description (optional):")
+                .with_default("")
+                .prompt()
+                .context("Failed to get description")?;
+
+            match validate_description(input.trim()) {
+                Ok(()) => break input.trim().to_string(),
+                Err(e) => println!("❌ {}", e),
+            }
+        };
+
+        let description = if description.is_empty() {
+            None
+        } else {
+            Some(description)
+        };
+
+        let mut vault = Vault::open(&self.ssh_dir)?;
+        vault.set(&token_key(&name), &token);
+        vault.save()?;
+        println!("✅ Token stored in the encrypted vault");
+
+        self.config.https_accounts.insert(name.clone(), HttpsAccount {
+            name: name.clone(),
+            email,
+            host: host.clone(),
+            username,
+            description,
+        });
+        self.save_config().context("Failed to save configuration")?;
+
+        let configure = Confirm::new(&format!("Use gam as git credential helper for https://{}?", host))
+            .with_default(true)
+            .prompt()
+            .context("Failed to get helper confirmation")?;
+
+        if configure {
+            Self::configure_credential_helper(&host)?;
+        }
+
+        println!("\n🎉 Account '{}' added successfully!", name);
+        Ok(())
+    }
+
+    fn configure_credential_helper(host: &str) -> Result<()> {
+        let helper = format!("credential.https://{}.helper", host);
+        let use_http_path = format!("credential.https://{}.useHttpPath", host);
+        let settings = [
+            // An empty helper resets inherited helpers so gam answers first
+            vec!["config", "--global", "--replace-all", helper.as_str(), ""],
+            vec!["config", "--global", "--add", helper.as_str(), "!gam credential"],
+            // Pass the repository path so routes can pick between accounts on one host
+            vec!["config", "--global", use_http_path.as_str(), "true"],
+        ];
+        for args in settings {
+            let status = Command::new("git")
+                .args(&args)
+                .status()
+                .context("Failed to run git config")?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("Failed to configure the git credential helper"));
+            }
+        }
+        println!("✅ git now asks gam for https://{} credentials", host);
+        Ok(())
+    }
+
+    pub(crate) fn list_https_accounts(&self) {
+        if self.config.https_accounts.is_empty() {
+            return;
+        }
+
+        println!("\n🌐 HTTPS Accounts:\n");
+
+        for (name, account) in &self.config.https_accounts {
+            let token = if Vault::contains(&self.ssh_dir, &token_key(name)) {
+                "token stored"
+            } else {
+                "no token"
+            };
+            println!("  ⚪ {} ({})", name, account.email);
+            println!("      Host: https://{} as {} ({})", account.host, account.username, token);
+            if let Some(desc) = &account.description {
+                println!("      Description: {}", desc);
+            }
+            println!();
+        }
+    }
+
+    pub(crate) fn remove_https_account(&mut self, name: &str) -> Result<()> {
+        if self.config.https_accounts.remove(name).is_none() {
+            return Err(anyhow::anyhow!("Account '{}' not found", name));
+        }
+        self.config.routes.retain(|route| route.account != name);
+        self.save_config().context("Failed to save configuration")?;
+
+        if let Err(e) = Vault::discard(&self.ssh_dir, &token_key(name)) {
+            println!("⚠️  Token for '{}' left in the vault: {}", name, e);
+        }
+
+        println!("✅ Account '{}' removed successfully!", name);
+        Ok(())
+    }
+
+    // Pick the HTTPS account for a request: explicit username, then route, then the only account on the host
+    pub(crate) fn https_account_for(&self, host: &str, path: Option<&str>, username: Option<&str>) -> Option<&HttpsAccount> {
+        let candidates: Vec<&HttpsAccount> = self.config.https_accounts.values()
+            .filter(|account| account.host.eq_ignore_ascii_case(host))
+            .filter(|account| username.is_none_or(|username| account.username == username))
+            .collect();
+
+        if let Some(route) = path.and_then(|path| self.route_for(host, path)) {
+            if let Some(account) = candidates.iter().find(|account| account.name == route.account) {
+                return Some(account);
+            }
+        }
+        match candidates.as_slice() {
+            [account] => Some(*account),
+            _ => None,
+        }
+    }
+
+    // `git credential` helper entry point; writes responses to stdout
+    pub(crate) fn credential(&self, action: CredentialAction) -> Result<()> {
+        let request = read_credential_request()?;
+        if request.get("protocol").map(|p| p.as_str()) != Some("https") {
+            return Ok(());
+        }
+        let Some(host) = request.get("host") else {
+            return Ok(());
+        };
+        let Some(account) = self.https_account_for(
+            host,
+            request.get("path").map(|p| p.as_str()),
+            request.get("username").map(|u| u.as_str()),
+        ) else {
+            return Ok(());
+        };
+
+        // Only now, for a host and account gam manages, may the vault be decrypted
+        let key = token_key(&account.name);
+        match action {
+            CredentialAction::Get => {
+                let token = if Vault::contains(&self.ssh_dir, &key) {
+                    Vault::open(&self.ssh_dir)?.get(&key).map(|token| token.to_string())
+                } else {
+                    None
+                };
+                println!("username={}", account.username);
+                if let Some(token) = token {
+                    println!("password={}", token);
+                }
+            }
+            CredentialAction::Store => {
+                // Skip when a token is already stored so successful pushes don't ask for the master password
+                if let Some(password) = request.get("password") {
+                    if !Vault::contains(&self.ssh_dir, &key) {
+                        let mut vault = Vault::open(&self.ssh_dir)?;
+                        vault.set(&key, password);
+                        vault.save()?;
+                    }
+                }
+            }
+            CredentialAction::Erase => {
+                if Vault::contains(&self.ssh_dir, &key) {
+                    let mut vault = Vault::open(&self.ssh_dir)?;
+                    vault.remove(&key);
+                    vault.save()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,438 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use crate::validate::{validate_host_without_port, validate_repository};
+use crate::vault::{token_key, Vault};
+use crate::{DeployKey, Endpoint, SshAccount, SshManager};
+
+// Token for the forge API; otherwise the vault token of an HTTPS account on the host is used
+const FORGE_TOKEN_ENV: &str = "GAM_FORGE_TOKEN";
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Forge {
+    Github,
+    Gitlab,
+}
+
+impl Forge {
+    // Self-hosted GitLab usually has "gitlab" in its name; everything else is treated as GitHub
+    fn detect(host: &str) -> Forge {
+        if host.to_ascii_lowercase().contains("gitlab") {
+            Forge::Gitlab
+        } else {
+            Forge::Github
+        }
+    }
+
+    fn api_base(self, host: &str) -> String {
+        match self {
+            Forge::Github if host.eq_ignore_ascii_case("github.com") => "https://api.github.com".to_string(),
+            Forge::Github => format!("https://{}/api/v3", host),
+            Forge::Gitlab => format!("https://{}/api/v4", host),
+        }
+    }
+
+    fn keys_url(self, host: &str, repository: &str) -> String {
+        match self {
+            Forge::Github => format!("{}/repos/{}/keys", self.api_base(host), repository),
+            Forge::Gitlab => format!(
+                "{}/projects/{}/deploy_keys",
+                self.api_base(host),
+                repository.replace('/', "%2F")
+            ),
+        }
+    }
+
+    fn auth_header(self, token: &str) -> String {
+        match self {
+            Forge::Github => format!("Authorization: Bearer {}", token),
+            Forge::Gitlab => format!("PRIVATE-TOKEN: {}", token),
+        }
+    }
+
+    fn register_body(self, title: &str, public_key: &str, read_only: bool) -> Value {
+        match self {
+            Forge::Github => json!({ "title": title, "key": public_key, "read_only": read_only }),
+            Forge::Gitlab => json!({ "title": title, "key": public_key, "can_push": !read_only }),
+        }
+    }
+}
+
+// Quoted value for a curl config file
+fn curl_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// A key registered on the forge during `deploy key add`, kept to undo it on a later failure
+struct Registration {
+    keys_url: String,
+    auth_header: String,
+    forge_id: Option<u64>,
+}
+
+// Call the forge API; the token goes through curl's config on stdin so it never shows up in `ps`
+fn forge_request(method: &str, url: &str, auth_header: &str, body: Option<&Value>) -> Result<(u16, String)> {
+    let mut config = format!(
+        "url = {}\nrequest = {}\nheader = {}\nheader = {}\n",
+        curl_quote(url),
+        curl_quote(method),
+        curl_quote("Accept: application/json"),
+        curl_quote(auth_header)
+    );
+    if let Some(body) = body {
+        config.push_str(&format!("header = {}\n", curl_quote("Content-Type: application/json")));
+        config.push_str(&format!("data = {}\n", curl_quote(&body.to_string())));
+    }
+
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--config", "-", "--write-out", "\n%{http_code}"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes()).context("Failed to pass the request to curl")?;
+    }
+    let output = child.wait_with_output().context("Failed to run curl")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("curl could not reach {}", url));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (response, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let status = status.trim().parse::<u16>().context("Unexpected response from curl")?;
+    Ok((status, response.to_string()))
+}
+
+// Error text of a failed API call, e.g. GitHub's {"message": "key is already in use"}
+fn api_error(status: u16, response: &str) -> anyhow::Error {
+    let message = serde_json::from_str::<Value>(response)
+        .ok()
+        .and_then(|value| {
+            let message = value.get("message").or_else(|| value.get("error"))?;
+            Some(message.as_str().map_or_else(|| message.to_string(), |text| text.to_string()))
+        })
+        .unwrap_or_else(|| response.trim().to_string());
+    anyhow::anyhow!("Forge API returned HTTP {}: {}", status, message)
+}
+
+fn deploy_key_id(host: &str, repository: &str) -> String {
+    format!("{}/{}", host.to_ascii_lowercase(), repository)
+}
+
+// Alias unique to the repository, e.g. "github-deploy-acme-api"
+fn deploy_alias(host: &str, repository: &str) -> String {
+    let host_prefix = host.split('.').next().unwrap_or(host);
+    format!("{}-deploy-{}", host_prefix, repository.replace('/', "-")).to_ascii_lowercase()
+}
+
+fn deploy_start_marker(alias: &str) -> String {
+    format!("# gam DEPLOY START [{}]\n", alias)
+}
+
+fn deploy_end_marker(alias: &str) -> String {
+    format!("# gam DEPLOY END [{}]\n", alias)
+}
+
+impl SshManager {
+    // GAM_FORGE_TOKEN, else the token of the given (or only matching) HTTPS account
+    fn forge_token(&self, host: &str, repository: &str, token_account: Option<&str>) -> Result<Option<String>> {
+        if let Ok(token) = env::var(FORGE_TOKEN_ENV) {
+            return Ok(Some(token));
+        }
+        let account = match token_account {
+            Some(name) => Some(self.config.https_accounts.get(name)
+                .with_context(|| format!("HTTPS account '{}' not found", name))?),
+            None => self.https_account_for(host, Some(repository), None),
+        };
+        let Some(account) = account else {
+            return Ok(None);
+        };
+        let key = token_key(&account.name);
+        if !Vault::contains(&self.ssh_dir, &key) {
+            return Ok(None);
+        }
+        Ok(Vault::open(&self.ssh_dir)?.get(&key).map(|token| token.to_string()))
+    }
+
+    pub(crate) fn deploy_key_add(
+        &mut self,
+        repository: &str,
+        host: &str,
+        write: bool,
+        forge: Option<Forge>,
+        token_account: Option<&str>,
+    ) -> Result<()> {
+        let repository = repository.trim().trim_end_matches(".git");
+        validate_repository(repository).map_err(|e| anyhow::anyhow!(e))?;
+        validate_host_without_port(host).map_err(|e| anyhow::anyhow!(e))?;
+        let id = deploy_key_id(host, repository);
+        if self.config.deploy_keys.contains_key(&id) {
+            return Err(anyhow::anyhow!("{} already has a deploy key; remove it first", id));
+        }
+        let alias = deploy_alias(host, repository);
+        self.check_alias(&alias, "")?;
+
+        let key_file = format!("id_deploy_{}_{}", host.replace('.', "_"), repository.replace('/', "_"));
+        let key_path = self.ssh_dir.join(&key_file);
+        if key_path.exists() || self.ssh_dir.join(format!("{}.pub", key_file)).exists() {
+            return Err(anyhow::anyhow!("Key {} already exists; refusing to overwrite it", key_path.display()));
+        }
+
+        let deploy_key = DeployKey {
+            host: host.to_string(),
+            repository: repository.to_string(),
+            key_file,
+            alias: alias.clone(),
+            read_only: !write,
+            forge_id: None,
+        };
+
+        // A failure past this point must not leave key files or a forge registration gam does not know about
+        let mut registration = None;
+        if let Err(e) = self.create_deploy_key(&id, deploy_key, forge, token_account, &mut registration) {
+            if let Some(registration) = registration {
+                self.undo_registration(&id, &registration);
+            }
+            let _ = self.remove_deploy_key_block(&alias);
+            let _ = fs::remove_file(&key_path);
+            let _ = fs::remove_file(format!("{}.pub", key_path.display()));
+            return Err(e);
+        }
+
+        let (_, endpoint) = self.deploy_endpoint(host);
+        println!("🔗 Clone with: git clone {}@{}:{}.git", endpoint.user, alias, repository);
+        Ok(())
+    }
+
+    // Generate the key, register it on the forge when a token is available and record it
+    fn create_deploy_key(
+        &mut self,
+        id: &str,
+        mut deploy_key: DeployKey,
+        forge: Option<Forge>,
+        token_account: Option<&str>,
+        registration: &mut Option<Registration>,
+    ) -> Result<()> {
+        let key_path = self.ssh_dir.join(&deploy_key.key_file);
+        let (host, repository) = (deploy_key.host.clone(), deploy_key.repository.clone());
+
+        // CI machines load deploy keys unattended, so they get no passphrase
+        println!("🔄 Generating deploy key for {}...", id);
+        let status = Command::new("ssh-keygen")
+            .arg("-t")
+            .arg("ed25519")
+            .arg("-C")
+            .arg(format!("gam deploy key for {}", id))
+            .arg("-f")
+            .arg(&key_path)
+            .arg("-N")
+            .arg("")
+            .arg("-q")
+            .status()
+            .context("Failed to run ssh-keygen")?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("ssh-keygen failed"));
+        }
+        #[cfg(unix)]
+        {
+            let perms = fs::Permissions::from_mode(0o600);
+            fs::set_permissions(&key_path, perms).context("Failed to set key permissions to 600")?;
+        }
+        let public_key = fs::read_to_string(format!("{}.pub", key_path.display()))
+            .context("Failed to read the public key")?
+            .trim()
+            .to_string();
+
+        let forge = forge.unwrap_or_else(|| Forge::detect(&host));
+        match self.forge_token(&host, &repository, token_account)? {
+            Some(token) => {
+                let write = !deploy_key.read_only;
+                let body = forge.register_body(&format!("gam: {}", deploy_key.alias), &public_key, !write);
+                let keys_url = forge.keys_url(&host, &repository);
+                let auth_header = forge.auth_header(&token);
+                let (status, response) = forge_request("POST", &keys_url, &auth_header, Some(&body))?;
+                if !(200..300).contains(&status) {
+                    return Err(api_error(status, &response));
+                }
+                deploy_key.forge_id = serde_json::from_str::<Value>(&response)
+                    .ok()
+                    .and_then(|value| value.get("id").and_then(Value::as_u64));
+                *registration = Some(Registration { keys_url, auth_header, forge_id: deploy_key.forge_id });
+                println!(
+                    "✅ Registered as a {} deploy key on {}",
+                    if write { "read-write" } else { "read-only" },
+                    id
+                );
+            }
+            None => {
+                println!("⚠️  No forge token found (set {} or add an HTTPS account for {})", FORGE_TOKEN_ENV, host);
+                println!("📋 Add this key to {} as a deploy key by hand:", id);
+                println!("{}", public_key);
+            }
+        }
+
+        self.write_deploy_key_block(&deploy_key)?;
+        self.config.deploy_keys.insert(id.to_string(), deploy_key);
+        if let Err(e) = self.save_config() {
+            self.config.deploy_keys.remove(id);
+            return Err(e).context("Failed to save configuration");
+        }
+        Ok(())
+    }
+
+    // Best effort: take back a registration whose key could not be recorded
+    fn undo_registration(&self, id: &str, registration: &Registration) {
+        let undone = registration.forge_id.is_some_and(|forge_id| {
+            let url = format!("{}/{}", registration.keys_url, forge_id);
+            matches!(
+                forge_request("DELETE", &url, &registration.auth_header, None),
+                Ok((200..=299 | 404, _))
+            )
+        });
+        if undone {
+            println!("ℹ️  Deploy key unregistered again from {}", id);
+        } else {
+            println!("⚠️  The new deploy key is still registered on {}; remove it there by hand", id);
+        }
+    }
+
+    // Deploy keys reach the same server as the accounts on their host, so a self-hosted forge's
+    // HostName, port, user, ProxyJump and extra options come from the account serving it
+    fn deploy_endpoint<'a>(&'a self, host: &'a str) -> (Option<&'a SshAccount>, Endpoint<'a>) {
+        let account = self.active_account_for_host(host)
+            .or_else(|| self.config.accounts.values().find(|account| account.serves_host(host)));
+        let endpoint = match account {
+            Some(account) => account.endpoint(host),
+            None => Endpoint { hostname: host, port: None, user: "git", proxy_jump: None },
+        };
+        (account, endpoint)
+    }
+
+    fn write_deploy_key_block(&self, deploy_key: &DeployKey) -> Result<()> {
+        let (account, endpoint) = self.deploy_endpoint(&deploy_key.host);
+
+        let ssh_config_path = self.ssh_dir.join("config");
+        let mut content = fs::read_to_string(&ssh_config_path).unwrap_or_default();
+        if !content.ends_with('\n') && !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(&deploy_start_marker(&deploy_key.alias));
+        content.push_str(&format!("Host {}\n", deploy_key.alias));
+        content.push_str(&format!("    HostName {}\n", endpoint.hostname));
+        if let Some(port) = endpoint.port {
+            content.push_str(&format!("    Port {}\n", port));
+        }
+        content.push_str(&format!("    User {}\n", endpoint.user));
+        content.push_str(&format!("    IdentityFile {}\n", self.ssh_dir.join(&deploy_key.key_file).display()));
+        if let Some(proxy_jump) = endpoint.proxy_jump {
+            content.push_str(&format!("    ProxyJump {}\n", proxy_jump));
+        }
+        for (key, value) in account.iter().flat_map(|account| &account.extra_options) {
+            if Self::check_ssh_option(key).is_ok() {
+                content.push_str(&format!("    {} {}\n", key, value));
+            }
+        }
+        content.push_str("    IdentitiesOnly yes\n");
+        content.push_str(&deploy_end_marker(&deploy_key.alias));
+        fs::write(&ssh_config_path, content).context("Failed to write SSH config")?;
+        Ok(())
+    }
+
+    fn remove_deploy_key_block(&self, alias: &str) -> Result<()> {
+        let ssh_config_path = self.ssh_dir.join("config");
+        let content = fs::read_to_string(&ssh_config_path).unwrap_or_default();
+        let start_marker = deploy_start_marker(alias);
+        let end_marker = deploy_end_marker(alias);
+        let Some(start_idx) = content.find(&start_marker) else {
+            return Ok(());
+        };
+        let end_idx = content[start_idx..]
+            .find(&end_marker)
+            .map_or(content.len(), |rel| start_idx + rel + end_marker.len());
+        let updated = format!("{}{}", &content[..start_idx], &content[end_idx..]);
+        fs::write(&ssh_config_path, updated).context("Failed to write SSH config")?;
+        Ok(())
+    }
+
+    pub(crate) fn list_deploy_keys(&self) {
+        if self.config.deploy_keys.is_empty() {
+            return;
+        }
+
+        println!("\n🚀 Deploy keys:\n");
+
+        for (id, deploy_key) in &self.config.deploy_keys {
+            let access = if deploy_key.read_only { "read-only" } else { "read-write" };
+            let fingerprint = Self::key_fingerprint(&self.ssh_dir.join(&deploy_key.key_file))
+                .unwrap_or_else(|| "missing".to_string());
+            println!("  ⚪ {} ({})", id, access);
+            let (_, endpoint) = self.deploy_endpoint(&deploy_key.host);
+            println!("      Remote: {}@{}:{}.git", endpoint.user, deploy_key.alias, deploy_key.repository);
+            println!("      Key: {} ({})", fingerprint, deploy_key.key_file);
+            match deploy_key.forge_id {
+                Some(forge_id) => println!("      Forge: registered (id {})", forge_id),
+                None => println!("      Forge: not registered by gam"),
+            }
+            println!();
+        }
+    }
+
+    pub(crate) fn deploy_key_list(&self) -> Result<()> {
+        if self.config.deploy_keys.is_empty() {
+            println!("📭 No deploy keys. Use 'gam deploy-key add <owner/repo>' to create one.");
+            return Ok(());
+        }
+        self.list_deploy_keys();
+        Ok(())
+    }
+
+    pub(crate) fn deploy_key_remove(
+        &mut self,
+        repository: &str,
+        host: &str,
+        forge: Option<Forge>,
+        token_account: Option<&str>,
+    ) -> Result<()> {
+        let repository = repository.trim().trim_end_matches(".git");
+        let id = deploy_key_id(host, repository);
+        let deploy_key = self.config.deploy_keys.get(&id)
+            .with_context(|| format!("No deploy key for {}", id))?
+            .clone();
+
+        let forge = forge.unwrap_or_else(|| Forge::detect(&deploy_key.host));
+        match (deploy_key.forge_id, self.forge_token(&deploy_key.host, repository, token_account)?) {
+            (Some(forge_id), Some(token)) => {
+                let url = format!("{}/{}", forge.keys_url(&deploy_key.host, repository), forge_id);
+                let (status, response) = forge_request("DELETE", &url, &forge.auth_header(&token), None)?;
+                match status {
+                    200..=299 => println!("✅ Deploy key unregistered from {}", id),
+                    404 => println!("ℹ️  Deploy key was already gone from {}", id),
+                    _ => return Err(api_error(status, &response)),
+                }
+            }
+            (Some(_), None) => println!(
+                "⚠️  No forge token found; remove the deploy key from {} by hand",
+                id
+            ),
+            (None, _) => println!("ℹ️  Remove the deploy key from {} by hand if you added it", id),
+        }
+
+        self.remove_deploy_key_block(&deploy_key.alias)?;
+        let key_path = self.ssh_dir.join(&deploy_key.key_file);
+        let _ = fs::remove_file(&key_path);
+        let _ = fs::remove_file(format!("{}.pub", key_path.display()));
+        self.config.deploy_keys.remove(&id);
+        self.save_config().context("Failed to save configuration")?;
+
+        println!("✅ Deploy key for {} removed", id);
+        Ok(())
+    }
+}
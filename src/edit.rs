@@ -0,0 +1,420 @@
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{Context, Result};
+use inquire::{Confirm, Select, Text};
+
+use crate::keyring::{keyring_clear, keyring_lookup, keyring_store};
+use crate::validate::{
+    validate_account_name, validate_description, validate_email, validate_host_without_port, validate_ssh_option_value,
+};
+use crate::vault::{passphrase_key, Vault};
+use crate::{SshAccount, SshManager, KNOWN_SSH_OPTIONS};
+
+// Field changes for `gam edit`; with none given, every field is prompted
+#[derive(clap::Args, Debug, Default)]
+pub(crate) struct AccountEdits {
+    /// New account name (renames the key files and alias)
+    #[arg(long)]
+    name: Option<String>,
+    /// New email address
+    #[arg(long)]
+    email: Option<String>,
+    /// New host, e.g. gitlab.com
+    #[arg(long)]
+    host: Option<String>,
+    /// New SSH alias ("" follows the alias template)
+    #[arg(long)]
+    alias: Option<String>,
+    /// New description ("" clears it)
+    #[arg(long)]
+    description: Option<String>,
+    /// Set an extra ssh_config option, e.g. --option ServerAliveInterval=60 (repeatable)
+    #[arg(long = "option", value_name = "KEY=VALUE")]
+    options: Vec<String>,
+    /// Remove an extra ssh_config option (repeatable)
+    #[arg(long = "remove-option", value_name = "KEY")]
+    remove_options: Vec<String>,
+}
+
+impl AccountEdits {
+    fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.email.is_none()
+            && self.host.is_none()
+            && self.alias.is_none()
+            && self.description.is_none()
+            && self.options.is_empty()
+            && self.remove_options.is_empty()
+    }
+}
+
+impl SshManager {
+    fn prompt_account_edits(account: &SshAccount) -> Result<AccountEdits> {
+        let name = Text::new("Account name:")
+            .with_default(&account.name)
+            .prompt()
+            .context("Failed to get account name")?;
+        let email = Text::new("Email address:")
+            .with_default(&account.email)
+            .prompt()
+            .context("Failed to get email")?;
+        let host = Text::new("Host:")
+            .with_default(&account.host)
+            .prompt()
+            .context("Failed to get host")?;
+        let alias = Text::new("SSH alias (empty follows the alias template):")
+            .with_default(account.alias.as_deref().unwrap_or(""))
+            .prompt()
+            .context("Failed to get SSH alias")?;
+        let description = Text::new("Description (optional):")
+            .with_default(account.description.as_deref().unwrap_or(""))
+            .prompt()
+            .context("Failed to get description")?;
+        Ok(AccountEdits {
+            name: Some(name),
+            email: Some(email),
+            host: Some(host),
+            alias: Some(alias),
+            description: Some(description),
+            ..AccountEdits::default()
+        })
+    }
+
+    pub(crate) fn edit_account(&mut self, account: Option<&str>, edits: AccountEdits) -> Result<()> {
+        if self.config.accounts.is_empty() {
+            println!("📭 No accounts found. Use 'gam add' to create one.");
+            return Ok(());
+        }
+
+        let selected = match account {
+            Some(name) => name.to_string(),
+            None => {
+                let account_names: Vec<String> = self.config.accounts.keys().cloned().collect();
+                Select::new("Select account to edit:", account_names)
+                    .prompt()
+                    .context("Failed to get account selection")?
+            }
+        };
+        let old = self.config.accounts.get(&selected)
+            .with_context(|| format!("Account '{}' not found", selected))?
+            .clone();
+
+        let interactive = edits.is_empty();
+        let edits = if interactive { Self::prompt_account_edits(&old)? } else { edits };
+
+        let mut new = old.clone();
+        if let Some(name) = edits.name {
+            new.name = name.trim().to_string();
+        }
+        if let Some(email) = edits.email {
+            new.email = email.trim().to_string();
+        }
+        if let Some(host) = edits.host {
+            new.host = host.trim().to_string();
+        }
+        // HostName, port, user and ProxyJump describe the old host's server
+        let mut dropped_endpoint = None;
+        if !new.host.eq_ignore_ascii_case(&old.host) {
+            if interactive {
+                (new.hostname, new.port, new.ssh_user, new.proxy_jump) = Self::prompt_endpoint(&new.host, None)?;
+            } else {
+                dropped_endpoint = Self::endpoint_summary(&old);
+                (new.hostname, new.port, new.ssh_user, new.proxy_jump) = (None, None, None, None);
+            }
+        }
+        if let Some(alias) = edits.alias {
+            let alias = alias.trim().to_string();
+            new.alias = Some(alias).filter(|alias| !alias.is_empty() && *alias != self.templated_alias(&new.name, &new.host));
+        }
+        if let Some(description) = edits.description {
+            new.description = Some(description).filter(|d| !d.trim().is_empty());
+        }
+        for key in &edits.remove_options {
+            let key = key.trim();
+            let Some(existing) = new.extra_options.keys().find(|existing| existing.eq_ignore_ascii_case(key)).cloned() else {
+                return Err(anyhow::anyhow!("'{}' has no extra option '{}'", old.name, key));
+            };
+            new.extra_options.remove(&existing);
+        }
+        for option in &edits.options {
+            let (key, value) = match option.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => (key.trim(), value.trim()),
+                _ => return Err(anyhow::anyhow!("Invalid option '{}'; use KEY=VALUE", option)),
+            };
+            let key = Self::check_ssh_option(key).map_err(|e| anyhow::anyhow!(e))?;
+            validate_ssh_option_value(value).map_err(|e| anyhow::anyhow!("{}: {}", key, e))?;
+            if !KNOWN_SSH_OPTIONS.contains(&key.as_str()) {
+                println!("⚠️  '{}' is not a known ssh_config option; ssh may reject it.", key);
+                let keep = Confirm::new("Keep it anyway?")
+                    .with_default(false)
+                    .prompt()
+                    .context("Failed to confirm unknown option")?;
+                if !keep {
+                    continue;
+                }
+            }
+            new.extra_options.retain(|existing, _| !existing.eq_ignore_ascii_case(&key));
+            new.extra_options.insert(key, value.to_string());
+        }
+
+        if !self.check_account_edit(&old, &new)? {
+            println!("ℹ️  Nothing to change for '{}'", old.name);
+            return Ok(());
+        }
+        self.apply_account_edit(&old, new.clone())?;
+
+        if let Some(summary) = dropped_endpoint {
+            println!("ℹ️  Dropped the connection settings of {} ({}); {} uses the defaults", old.host, summary, new.host);
+        }
+        for (_, old_alias, new_alias) in self.changed_aliases(&old, &new) {
+            println!(
+                "⚠️  Alias changed from {} to {}; update remotes that still use the old alias (or use 'gam rename')",
+                old_alias, new_alias
+            );
+        }
+        println!("✅ Account '{}' updated", new.name);
+        Ok(())
+    }
+
+    // Validate an edit; Ok(false) when it changes nothing
+    pub(crate) fn check_account_edit(&self, old: &SshAccount, new: &SshAccount) -> Result<bool> {
+        if new.name != old.name {
+            validate_account_name(&new.name).map_err(|e| anyhow::anyhow!(e))?;
+        }
+        if new.name != old.name
+            && (self.config.accounts.contains_key(&new.name) || self.config.https_accounts.contains_key(&new.name))
+        {
+            return Err(anyhow::anyhow!("Account '{}' already exists", new.name));
+        }
+        if new.email != old.email {
+            validate_email(&new.email).map_err(|e| anyhow::anyhow!(e))?;
+        }
+        if new.host != old.host {
+            validate_host_without_port(&new.host).map_err(|e| anyhow::anyhow!(e))?;
+        }
+        if new.description != old.description {
+            validate_description(new.description.as_deref().unwrap_or_default()).map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        if !new.host.eq_ignore_ascii_case(&old.host) {
+            let old_names: Vec<String> = std::iter::once(&old.host).chain(&old.hostname)
+                .map(|name| name.to_ascii_lowercase())
+                .collect();
+            for (key, value) in &new.extra_options {
+                let leftover = old.extra_options.get(key) == Some(value);
+                if let Some(name) = old_names.iter().find(|name| leftover && value.to_ascii_lowercase().contains(name.as_str())) {
+                    return Err(anyhow::anyhow!(
+                        "Extra option '{} {}' still points at {}; change it with --option or drop it with --remove-option",
+                        key, value, name
+                    ));
+                }
+            }
+        }
+
+        for (_, _, new_alias) in self.changed_aliases(old, new) {
+            self.check_alias(&new_alias, &old.name)?;
+        }
+
+        // Refuse before anything moves rather than stop halfway through the renames
+        for (_, new_file) in Self::key_file_renames(old, new) {
+            for path in [self.ssh_dir.join(&new_file), self.ssh_dir.join(format!("{}.pub", new_file))] {
+                if path.exists() {
+                    return Err(anyhow::anyhow!("Key {} already exists; refusing to overwrite it", path.display()));
+                }
+            }
+        }
+
+        Ok(new.name != old.name
+            || new.host != old.host
+            || new.email != old.email
+            || new.alias != old.alias
+            || new.description != old.description
+            || new.extra_options != old.extra_options
+            || new.host_aliases != old.host_aliases)
+    }
+
+    // Store an edited account and bring key files, SSH config, bindings, routes and secrets in line
+    pub(crate) fn apply_account_edit(&mut self, old: &SshAccount, mut new: SshAccount) -> Result<()> {
+        let renamed = new.name != old.name;
+        let moved = !new.host.eq_ignore_ascii_case(&old.host);
+
+        let key_renames = Self::key_file_renames(old, &new);
+        for (old_file, new_file) in &key_renames {
+            if new.key_file == *old_file {
+                new.key_file = new_file.clone();
+            }
+            for key in new.keys.iter_mut().filter(|key| key.file == *old_file) {
+                key.file = new_file.clone();
+            }
+        }
+
+        let ssh_config = fs::read_to_string(self.ssh_dir.join("config")).unwrap_or_default();
+        let had_alias_block = ssh_config.contains(&format!("# {} - ", old.name));
+        let active_hosts = self.active_hosts_of(old);
+
+        // Rewrites embed the alias and host, so drop them while the old values are still in place
+        let routes: Vec<_> = self.config.routes.iter()
+            .filter(|route| route.account == old.name)
+            .cloned()
+            .collect();
+        for route in &routes {
+            self.unset_route_rewrite(route)?;
+        }
+
+        // Taking back an alias retired by an earlier rename ends its grace period early
+        for (_, new_alias) in self.aliases_of(&new) {
+            if self.config.deprecated_aliases.iter().any(|entry| entry.alias == new_alias) {
+                self.config.deprecated_aliases.retain(|entry| entry.alias != new_alias);
+                self.remove_deprecated_alias(&new_alias)?;
+            }
+        }
+
+        self.config.accounts.remove(&old.name);
+        self.config.accounts.insert(new.name.clone(), new.clone());
+        if renamed {
+            for bound in self.config.directories.values_mut() {
+                if *bound == old.name {
+                    *bound = new.name.clone();
+                }
+            }
+            for route in self.config.routes.iter_mut() {
+                if route.account == old.name {
+                    route.account = new.name.clone();
+                }
+            }
+            for deprecated in self.config.deprecated_aliases.iter_mut() {
+                if deprecated.account == old.name {
+                    deprecated.account = new.name.clone();
+                }
+            }
+            for profile in self.config.profiles.values_mut() {
+                for account in profile.accounts.iter_mut() {
+                    if *account == old.name {
+                        *account = new.name.clone();
+                    }
+                }
+            }
+        }
+        // A moved primary host takes its active mapping along
+        let active_on_old_host = active_hosts.iter().any(|host| host.eq_ignore_ascii_case(&old.host));
+        let active_hosts: Vec<String> = active_hosts.into_iter()
+            .map(|host| if host.eq_ignore_ascii_case(&old.host) { new.host.clone() } else { host })
+            .collect();
+        for host in old.hosts() {
+            if self.config.active_hosts.get(&host.to_ascii_lowercase()) == Some(&old.name) {
+                self.unset_active_host(host);
+            }
+        }
+        for host in &active_hosts {
+            self.set_active_host(host, &new.name);
+        }
+        // The key files and gam_config.json move together: a failed save puts the files back
+        self.rename_key_files(&key_renames)?;
+        if let Err(e) = self.save_config() {
+            self.restore_key_files(&key_renames);
+            return Err(e.context("Failed to save configuration"));
+        }
+        for (_, new_file) in &key_renames {
+            println!("✅ Key renamed to {}", self.ssh_dir.join(new_file).display());
+        }
+
+        if renamed {
+            self.rename_stored_passphrase(&old.name, &new.name)?;
+        }
+
+        if had_alias_block {
+            self.remove_ssh_config_for_account(old)?;
+            self.update_ssh_config(&new.name)?;
+        }
+        if moved && active_on_old_host {
+            self.clear_active_mapping_for_host(&old.host)?;
+        }
+        for host in &active_hosts {
+            self.upsert_active_mapping_for_host(&new, host)?;
+        }
+        for route in self.config.routes.iter().filter(|route| route.account == new.name) {
+            self.apply_route_rewrite(route)?;
+        }
+        // Retired aliases must follow the account's key and endpoint too
+        for deprecated in self.config.deprecated_aliases.iter().filter(|entry| entry.account == new.name) {
+            self.write_deprecated_alias(deprecated)?;
+        }
+        Ok(())
+    }
+
+    // Keys generated by gam follow the account's name and host; custom key files keep their name
+    fn key_file_renames(old: &SshAccount, new: &SshAccount) -> Vec<(String, String)> {
+        if new.name == old.name && new.host.eq_ignore_ascii_case(&old.host) {
+            return Vec::new();
+        }
+        let old_base = Self::default_key_file(&old.name, &old.host);
+        let new_base = Self::default_key_file(&new.name, &new.host);
+        let mut renames = Vec::new();
+        if old.key_file == old_base {
+            renames.push((old_base.clone(), new_base.clone()));
+        }
+        for key in &old.keys {
+            if key.file == format!("{}_{}", old_base, key.label) {
+                renames.push((key.file.clone(), format!("{}_{}", new_base, key.label)));
+            }
+        }
+        renames
+    }
+
+    // Private and public halves of each renamed key, as (from, to) paths
+    fn key_file_moves(&self, renames: &[(String, String)]) -> Vec<(PathBuf, PathBuf)> {
+        renames.iter()
+            .flat_map(|(old_file, new_file)| {
+                [
+                    (self.ssh_dir.join(old_file), self.ssh_dir.join(new_file)),
+                    (self.ssh_dir.join(format!("{}.pub", old_file)), self.ssh_dir.join(format!("{}.pub", new_file))),
+                ]
+            })
+            .collect()
+    }
+
+    // All or nothing: a failed rename moves the files already renamed back
+    fn rename_key_files(&self, renames: &[(String, String)]) -> Result<()> {
+        let moves = self.key_file_moves(renames);
+        if let Some((_, taken)) = moves.iter().find(|(_, to)| to.exists()) {
+            return Err(anyhow::anyhow!("Key {} already exists; refusing to overwrite it", taken.display()));
+        }
+        let mut done = Vec::new();
+        for (from, to) in moves.iter().filter(|(from, _)| from.exists()) {
+            if let Err(e) = fs::rename(from, to) {
+                for (from, to) in done.iter().rev() {
+                    let _ = fs::rename(to, from);
+                }
+                return Err(e).with_context(|| format!("Failed to rename {}", from.display()));
+            }
+            done.push((from, to));
+        }
+        Ok(())
+    }
+
+    fn restore_key_files(&self, renames: &[(String, String)]) {
+        for (from, to) in self.key_file_moves(renames).iter().rev() {
+            if to.exists() && !from.exists() {
+                let _ = fs::rename(to, from);
+            }
+        }
+    }
+
+    // Stored secrets are keyed by account name
+    fn rename_stored_passphrase(&self, old_name: &str, new_name: &str) -> Result<()> {
+        if let Some(passphrase) = keyring_lookup(old_name) {
+            keyring_store(new_name, &passphrase)?;
+            keyring_clear(old_name);
+        }
+        let old_key = passphrase_key(old_name);
+        if Vault::contains(&self.ssh_dir, &old_key) {
+            let mut vault = Vault::open(&self.ssh_dir)?;
+            if let Some(passphrase) = vault.get(&old_key).map(|p| p.to_string()) {
+                vault.set(&passphrase_key(new_name), &passphrase);
+                vault.remove(&old_key);
+                vault.save()?;
+            }
+        }
+        Ok(())
+    }
+}
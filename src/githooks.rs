@@ -0,0 +1,286 @@
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use std::process::Command;
+use anyhow::{Context, Result};
+use inquire::Confirm;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use crate::project::git_user_email;
+use crate::{SshAccount, SshManager};
+
+const HOOK_MARKER: &str = "# gam-managed hook";
+const HOOK_NAMES: &[&str] = &["pre-commit", "pre-push"];
+
+// Host and repository path of an SSH remote URL
+pub(crate) struct SshRemote {
+    pub(crate) host: String,
+    pub(crate) path: String,
+}
+
+// Parse `git@host:org/repo.git` and `ssh://git@host:port/org/repo.git`; None for non-SSH URLs
+pub(crate) fn parse_ssh_remote(url: &str) -> Option<SshRemote> {
+    let (authority, path) = if let Some(rest) = url
+        .strip_prefix("ssh://")
+        .or_else(|| url.strip_prefix("git+ssh://"))
+        .or_else(|| url.strip_prefix("ssh+git://"))
+    {
+        let (authority, path) = rest.split_once('/')?;
+        let authority = authority.rsplit_once(':').map_or(authority, |(host, _port)| host);
+        (authority, path)
+    } else if url.contains("://") {
+        return None;
+    } else {
+        let (authority, path) = url.split_once(':')?;
+        if authority.contains('/') {
+            return None;
+        }
+        (authority, path)
+    };
+
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    if host.is_empty() {
+        return None;
+    }
+    Some(SshRemote {
+        host: host.to_string(),
+        path: path.trim_start_matches('/').to_string(),
+    })
+}
+
+fn hook_script(name: &str, chain_local: bool) -> String {
+    // pre-push gets the pushed refs on stdin; read them once so a chained hook sees them too
+    let (verify, run_local) = match name {
+        "pre-push" => (
+            "refs=$(cat)\nprintf '%s\\n' \"$refs\" | gam verify --remote \"$1\" --url \"$2\" --push || exit 1",
+            "printf '%s\\n' \"$refs\" | \"$local_hook\" \"$@\"\n    exit $?",
+        ),
+        _ => ("gam verify || exit 1", "exec \"$local_hook\" \"$@\""),
+    };
+    let mut script = format!("#!/bin/sh\n{}\n{}\n", HOOK_MARKER, verify);
+    if chain_local {
+        // core.hooksPath disables .git/hooks, so keep running the repository's own hook
+        script.push_str(&format!(
+            "local_hook=\"$(git rev-parse --git-dir)/hooks/{}\"\nif [ -x \"$local_hook\" ]; then\n    {}\nfi\n",
+            name, run_local
+        ));
+    }
+    script
+}
+
+// URL of `remote`, else of the current branch's remote, else of origin
+pub(crate) fn remote_url(remote: Option<&str>) -> Option<String> {
+    let remote = remote.map(|r| r.to_string()).or_else(|| {
+        git_output(&["config", "--get", &format!("branch.{}.remote", git_output(&["branch", "--show-current"])?)])
+    }).unwrap_or_else(|| "origin".to_string());
+    git_output(&["remote", "get-url", &remote])
+}
+
+fn git_output(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if text.is_empty() { None } else { Some(text) }
+}
+
+impl SshManager {
+    fn global_hooks_dir(&self) -> PathBuf {
+        self.ssh_dir.join("gam").join("hooks")
+    }
+
+    fn hooks_dir(&self, global: bool) -> Result<PathBuf> {
+        if global {
+            return Ok(self.global_hooks_dir());
+        }
+        let dir = git_output(&["rev-parse", "--git-path", "hooks"])
+            .context("Not inside a git repository")?;
+        Ok(PathBuf::from(dir))
+    }
+
+    pub(crate) fn install_hooks(&self, global: bool) -> Result<()> {
+        let dir = self.hooks_dir(global)?;
+        fs::create_dir_all(&dir).context("Failed to create hooks directory")?;
+
+        for name in HOOK_NAMES {
+            let path = dir.join(name);
+            if path.exists() {
+                let existing = fs::read_to_string(&path).unwrap_or_default();
+                if !existing.contains(HOOK_MARKER) {
+                    let overwrite = Confirm::new(&format!("{} already exists. Overwrite?", path.display()))
+                        .with_default(false)
+                        .prompt()
+                        .context("Failed to confirm overwrite")?;
+                    if !overwrite {
+                        println!("ℹ️  Skipping {}", path.display());
+                        continue;
+                    }
+                }
+            }
+
+            fs::write(&path, hook_script(name, global))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            #[cfg(unix)]
+            {
+                let perms = fs::Permissions::from_mode(0o755);
+                fs::set_permissions(&path, perms).context("Failed to make hook executable")?;
+            }
+            println!("✅ Installed {}", path.display());
+        }
+
+        if global {
+            let status = Command::new("git")
+                .args(["config", "--global", "core.hooksPath"])
+                .arg(&dir)
+                .status()
+                .context("Failed to run git config")?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("Failed to set core.hooksPath"));
+            }
+            println!("✅ core.hooksPath set to {}", dir.display());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn uninstall_hooks(&self, global: bool) -> Result<()> {
+        let dir = self.hooks_dir(global)?;
+        for name in HOOK_NAMES {
+            let path = dir.join(name);
+            let managed = fs::read_to_string(&path)
+                .map(|content| content.contains(HOOK_MARKER))
+                .unwrap_or(false);
+            if managed {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                println!("✅ Removed {}", path.display());
+            }
+        }
+
+        if global {
+            let current = git_output(&["config", "--global", "core.hooksPath"]);
+            if current.as_deref() == Some(dir.display().to_string().as_str()) {
+                let _ = Command::new("git")
+                    .args(["config", "--global", "--unset", "core.hooksPath"])
+                    .status();
+                println!("✅ core.hooksPath unset");
+            }
+        }
+        Ok(())
+    }
+
+    // Whether gam manages any alias or active mapping for `host`
+    fn manages_ssh_host(&self, host: &str) -> bool {
+        self.account_for_alias(host).is_some()
+            || self.config.accounts.values().any(|account| account.serves_host(host))
+    }
+
+    fn accounts_with_email(&self, email: &str) -> Vec<&SshAccount> {
+        self.config.accounts.values()
+            .filter(|account| account.email.eq_ignore_ascii_case(email))
+            .collect()
+    }
+
+    // Reject commits/pushes whose email and SSH key belong to different gam accounts
+    pub(crate) fn verify(&self, remote: Option<&str>, url: Option<&str>, push: bool) -> Result<()> {
+        let mut emails = Vec::new();
+        if push {
+            emails.extend(Self::pushed_committer_emails(remote)?);
+        } else {
+            let email = git_user_email()
+                .context("git user.email is not set; configure it before committing")?;
+            emails.push(email);
+        }
+
+        // Colleagues' commits (merges, upstream history) carry emails gam knows nothing about
+        emails.retain(|email| {
+            let known = !self.accounts_with_email(email).is_empty();
+            if !known {
+                eprintln!("⚠️  gam: commit email {} does not belong to any gam account", email);
+            }
+            known
+        });
+        if emails.is_empty() {
+            return Ok(());
+        }
+
+        let url = match url {
+            Some(url) => Some(url.to_string()),
+            None => remote_url(remote),
+        };
+
+        let Some(remote) = url.as_deref().and_then(parse_ssh_remote) else {
+            // No SSH remote yet (or HTTPS): only the email can be checked
+            return Ok(());
+        };
+        if !self.manages_ssh_host(&remote.host) {
+            return Ok(());
+        }
+
+        let Some(account) = self.account_for_ssh_target(&remote.host, Some(&remote.path)) else {
+            return Err(anyhow::anyhow!(
+                "gam: no active account for {}; run 'gam switch' or use an account alias in the remote URL",
+                remote.host
+            ));
+        };
+
+        for email in &emails {
+            if !account.email.eq_ignore_ascii_case(email) {
+                let owners: Vec<&str> = self.accounts_with_email(email).iter().map(|a| a.name.as_str()).collect();
+                return Err(anyhow::anyhow!(
+                    "gam: commit email {} belongs to '{}', but {} uses the key of '{}' ({}).\n\
+                     Fix it with 'git config user.email {}' or point the remote at the right account alias.",
+                    email,
+                    owners.join("', '"),
+                    remote.host,
+                    account.name,
+                    account.email,
+                    account.email
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Committer emails of the commits a pre-push hook is about to send (refs arrive on stdin);
+    // committers rather than authors, so rebased or cherry-picked work is checked as the pusher's
+    fn pushed_committer_emails(remote: Option<&str>) -> Result<Vec<String>> {
+        let mut emails: Vec<String> = Vec::new();
+
+        for line in io::stdin().lock().lines() {
+            let line = line.context("Failed to read pushed refs")?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [_, local_sha, _, remote_sha] = fields[..] else {
+                continue;
+            };
+            if local_sha.trim_matches('0').is_empty() {
+                // Deleting a remote branch pushes no commits
+                continue;
+            }
+
+            let range = if remote_sha.trim_matches('0').is_empty() {
+                let not_remote = format!("--remotes={}", remote.unwrap_or("origin"));
+                vec![local_sha.to_string(), "--not".to_string(), not_remote]
+            } else {
+                vec![format!("{}..{}", remote_sha, local_sha)]
+            };
+
+            let mut args = vec!["log".to_string(), "--format=%ce".to_string()];
+            args.extend(range);
+            let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+            if let Some(output) = git_output(&args) {
+                for email in output.lines() {
+                    if !emails.iter().any(|e| e.eq_ignore_ascii_case(email)) {
+                        emails.push(email.to_string());
+                    }
+                }
+            }
+        }
+
+        if emails.is_empty() {
+            emails.extend(git_user_email());
+        }
+        Ok(emails)
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+use inquire::Confirm;
+
+use crate::validate::validate_host_without_port;
+use crate::active::ActiveBlockState;
+use crate::{SshAccount, SshManager};
+
+// Host key fingerprints published by the major forges (host, key type, SHA256 fingerprint)
+const PUBLISHED_HOST_KEYS: &[(&str, &str, &str)] = &[
+    ("github.com", "ssh-ed25519", "SHA256:+DiY3wvvV6TuJJhbpZisF/zLDA0zPMSvHdkr4UvCOqU"),
+    ("github.com", "ecdsa-sha2-nistp256", "SHA256:p2QAMXNIC1TJYWeIOttrVc98/R1BUFWu3/LiyKgUfQM"),
+    ("github.com", "ssh-rsa", "SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s"),
+    ("gitlab.com", "ssh-ed25519", "SHA256:eUXGGm1YGsMAS7vkcx6JOJdOGHPem5gQp4taiCfCLB8"),
+    ("gitlab.com", "ecdsa-sha2-nistp256", "SHA256:HbW3g8zUjNSksFbqTiUWPWg2Bq1x8xdGUrliXFzSnUw"),
+    ("gitlab.com", "ssh-rsa", "SHA256:ROQFvPThGrW4RuWLoL9tq9I9zJ42fK4XywyRtbOz/EQ"),
+    ("bitbucket.org", "ssh-ed25519", "SHA256:ybgmFkzwOSotHTHLJgHO0QN8L0xErw6vd0VhFA9m3SM"),
+    ("bitbucket.org", "ecdsa-sha2-nistp256", "SHA256:FC73VB6C4OQLSCrjEayhMp9UMxS97caD/Yyi2bhW/J0"),
+    ("bitbucket.org", "ssh-rsa", "SHA256:46OSHA1Rmj8E8ERTC6xkNcmGOw9oFxYr0WF6zWW8l1E"),
+];
+
+// A host key as returned by ssh-keyscan, e.g. "[git.corp.example]:2222 ssh-ed25519 AAAA..."
+struct ScannedKey {
+    key_type: String,
+    key: String,
+    fingerprint: String,
+}
+
+impl SshManager {
+    // HostKeyAlias from the account's extra options, which replaces host and port in known_hosts
+    pub(crate) fn host_key_alias(account: &SshAccount) -> Option<&str> {
+        account.extra_options.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("HostKeyAlias"))
+            .map(|(_, alias)| alias.as_str())
+    }
+
+    // Name under which ssh looks one of the account's servers up in known_hosts
+    pub(crate) fn known_hosts_name(account: &SshAccount, host: &str) -> String {
+        if let Some(alias) = Self::host_key_alias(account) {
+            return alias.to_string();
+        }
+        let endpoint = account.endpoint(host);
+        match endpoint.port {
+            Some(port) if port != 22 => format!("[{}]:{}", endpoint.hostname, port),
+            _ => endpoint.hostname.to_string(),
+        }
+    }
+
+    fn fingerprint_of(key_line: &str) -> Result<String> {
+        let mut child = Command::new("ssh-keygen")
+            .arg("-lf")
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to run ssh-keygen")?;
+        if let Some(stdin) = child.stdin.as_mut() {
+            writeln!(stdin, "{}", key_line).context("Failed to pass key to ssh-keygen")?;
+        }
+        let output = child.wait_with_output().context("Failed to run ssh-keygen")?;
+
+        // Output: "256 SHA256:... host (ED25519)"
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .nth(1)
+            .map(|fp| fp.to_string())
+            .context("Failed to compute host key fingerprint")
+    }
+
+    fn scan_host_keys(account: &SshAccount, host: &str) -> Result<Vec<ScannedKey>> {
+        let endpoint = account.endpoint(host);
+        let mut cmd = Command::new("ssh-keyscan");
+        cmd.arg("-t").arg("ed25519,ecdsa,rsa");
+        if let Some(port) = endpoint.port {
+            cmd.arg("-p").arg(port.to_string());
+        }
+        let output = cmd
+            .arg(endpoint.hostname)
+            .stderr(Stdio::null())
+            .output()
+            .context("Failed to run ssh-keyscan")?;
+
+        let mut keys = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let (Some(_), Some(key_type), Some(key)) = (parts.next(), parts.next(), parts.next()) else {
+                continue;
+            };
+            keys.push(ScannedKey {
+                key_type: key_type.to_string(),
+                key: key.to_string(),
+                fingerprint: Self::fingerprint_of(line)?,
+            });
+        }
+        Ok(keys)
+    }
+
+    // Keys currently recorded in known_hosts for the given name, as "type base64" pairs
+    pub(crate) fn known_host_keys(&self, name: &str) -> Vec<(String, String)> {
+        let known_hosts = self.ssh_dir.join("known_hosts");
+        if !known_hosts.exists() {
+            return Vec::new();
+        }
+        let output = Command::new("ssh-keygen")
+            .arg("-F")
+            .arg(name)
+            .arg("-f")
+            .arg(&known_hosts)
+            .stderr(Stdio::null())
+            .output();
+
+        let Ok(output) = output else {
+            return Vec::new();
+        };
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace().skip(1);
+                Some((parts.next()?.to_string(), parts.next()?.to_string()))
+            })
+            .collect()
+    }
+
+    pub(crate) fn trust_host(&self, account_name: &str) -> Result<()> {
+        let account = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?;
+        for host in account.hosts() {
+            self.trust_host_keys(account, host)?;
+        }
+        Ok(())
+    }
+
+    fn trust_host_keys(&self, account: &SshAccount, host: &str) -> Result<()> {
+        let name = Self::known_hosts_name(account, host);
+
+        println!("\n🔄 Fetching host keys for {}...", name);
+        let keys = Self::scan_host_keys(account, host)?;
+        if keys.is_empty() {
+            return Err(anyhow::anyhow!("ssh-keyscan returned no host keys for {}", name));
+        }
+
+        let endpoint = account.endpoint(host);
+        let published: Vec<_> = PUBLISHED_HOST_KEYS
+            .iter()
+            .filter(|(host, _, _)| *host == endpoint.hostname && endpoint.port.unwrap_or(22) == 22)
+            .collect();
+
+        let mut mismatches = 0;
+        for key in &keys {
+            let status = match published.iter().find(|(_, key_type, _)| *key_type == key.key_type) {
+                Some((_, _, fingerprint)) if *fingerprint == key.fingerprint => "✅ matches published fingerprint",
+                Some(_) => {
+                    mismatches += 1;
+                    "❌ DOES NOT match published fingerprint"
+                }
+                None if published.is_empty() => "",
+                None => "ℹ️  no published fingerprint",
+            };
+            println!("{}", format!("  {} {} {}", key.key_type, key.fingerprint, status).trim_end());
+        }
+
+        if mismatches > 0 {
+            return Err(anyhow::anyhow!(
+                "Host keys for {} do not match the published fingerprints; refusing to trust them",
+                name
+            ));
+        }
+
+        let existing = self.known_host_keys(&name);
+        let all_known = keys
+            .iter()
+            .all(|key| existing.iter().any(|(t, k)| *t == key.key_type && *k == key.key));
+        if all_known {
+            println!("✅ Host keys for {} are already trusted.", name);
+            return Ok(());
+        }
+
+        // Servers may publish several keys of one type; a key changed when one recorded for a
+        // scanned type is no longer offered, while extra keys of that type are just new
+        let changed = keys.iter().any(|key| {
+            let scanned: BTreeSet<&str> = keys.iter()
+                .filter(|other| other.key_type == key.key_type)
+                .map(|other| other.key.as_str())
+                .collect();
+            existing.iter().any(|(t, k)| *t == key.key_type && !scanned.contains(k.as_str()))
+        });
+        if changed {
+            println!("⚠️  known_hosts already has different keys for {}; the host key may have changed.", name);
+        }
+
+        if published.is_empty() || changed {
+            let trust = Confirm::new(&format!("Trust these host keys for {}?", name))
+                .with_default(!changed)
+                .prompt()
+                .context("Failed to get trust confirmation")?;
+            if !trust {
+                println!("❌ Host keys not trusted.");
+                return Ok(());
+            }
+        }
+
+        let known_hosts = self.ssh_dir.join("known_hosts");
+        if changed {
+            let _ = Command::new("ssh-keygen")
+                .arg("-R")
+                .arg(&name)
+                .arg("-f")
+                .arg(&known_hosts)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+
+        let mut content = if known_hosts.exists() {
+            fs::read_to_string(&known_hosts).context("Failed to read known_hosts")?
+        } else {
+            String::new()
+        };
+        if !content.ends_with('\n') && !content.is_empty() {
+            content.push('\n');
+        }
+        for key in &keys {
+            let known = existing.iter().any(|(t, k)| *t == key.key_type && *k == key.key);
+            if known && !changed {
+                continue;
+            }
+            // ssh-keyscan names the scanned address; ssh looks the key up under `name`
+            content.push_str(&format!("{} {} {}\n", name, key.key_type, key.key));
+        }
+        fs::write(&known_hosts, content).context("Failed to write known_hosts")?;
+
+        println!("✅ Host keys for {} added to known_hosts", name);
+        Ok(())
+    }
+
+    // Serve another host with the account's key, e.g. a GitHub Enterprise next to github.com
+    pub(crate) fn hosts_add(&mut self, account_name: &str, host: &str) -> Result<()> {
+        let host = host.trim().to_string();
+        validate_host_without_port(&host).map_err(|e| anyhow::anyhow!(e))?;
+        let account = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?
+            .clone();
+        if account.serves_host(&host) {
+            println!("ℹ️  '{}' already serves {}", account_name, host);
+            return Ok(());
+        }
+        let mut updated = account.clone();
+        updated.extra_hosts.push(host.clone());
+        let alias = self.alias_for_host(&updated, &host);
+        if self.aliases_of(&account).iter().any(|(_, existing)| *existing == alias) {
+            return Err(anyhow::anyhow!("Alias '{}' is already used by '{}'", alias, account_name));
+        }
+        self.check_alias(&alias, &account.name)?;
+
+        let ssh_config = fs::read_to_string(self.ssh_dir.join("config")).unwrap_or_default();
+        let had_alias_block = ssh_config.contains(&format!("# {} - ", account.name));
+        let was_active = self.active_account_for_host(&account.host).is_some_and(|active| active.name == account.name);
+        let activate = was_active && self.confirm_take_over_host(&host, account_name)?;
+
+        self.config.accounts.insert(updated.name.clone(), updated.clone());
+        self.save_config().context("Failed to save configuration")?;
+
+        if had_alias_block {
+            self.update_ssh_config(&updated.name)?;
+        }
+        if activate {
+            self.set_active_host(&host, &updated.name);
+            self.save_config().context("Failed to save configuration")?;
+            self.upsert_active_mapping_for_host(&updated, &host)?;
+        }
+        // Routes on the new host can now be rewritten to the account's alias
+        self.reapply_route_rewrites(account_name)?;
+        println!("✅ '{}' now serves {} (alias {})", account_name, host, alias);
+        println!("ℹ️  Run 'gam hosts trust {}' to trust the new host's keys", account_name);
+        Ok(())
+    }
+
+    pub(crate) fn hosts_remove(&mut self, account_name: &str, host: &str) -> Result<()> {
+        let account = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?
+            .clone();
+        if account.host.eq_ignore_ascii_case(host) {
+            return Err(anyhow::anyhow!(
+                "{} is the primary host of '{}'; change it with 'gam edit {} --host'",
+                host,
+                account_name,
+                account_name
+            ));
+        }
+        let Some(index) = account.extra_hosts.iter().position(|extra| extra.eq_ignore_ascii_case(host)) else {
+            return Err(anyhow::anyhow!("'{}' does not serve {}", account_name, host));
+        };
+        let was_active = self.active_account_for_host(host).is_some_and(|active| active.name == account.name);
+        let alias = self.alias_for_host(&account, host);
+
+        // Rewrites point at the alias being removed, so drop them while it still resolves
+        self.unset_route_rewrites(account_name)?;
+        self.remove_alias_block(&account.name, &alias)?;
+        if was_active {
+            self.clear_active_mapping_for_host(host)?;
+            self.unset_active_host(host);
+        }
+        let mut updated = account;
+        updated.extra_hosts.remove(index);
+        updated.host_aliases.remove(&host.to_ascii_lowercase());
+        self.config.accounts.insert(updated.name.clone(), updated);
+        self.save_config().context("Failed to save configuration")?;
+        self.reapply_route_rewrites(account_name)?;
+
+        println!("✅ '{}' no longer serves {}", account_name, host);
+        Ok(())
+    }
+
+    fn unset_route_rewrites(&self, account_name: &str) -> Result<()> {
+        for route in self.config.routes.iter().filter(|route| route.account == account_name) {
+            self.unset_route_rewrite(route)?;
+        }
+        Ok(())
+    }
+
+    // Rewrite the account's routes for its current hosts and aliases
+    fn reapply_route_rewrites(&self, account_name: &str) -> Result<()> {
+        self.unset_route_rewrites(account_name)?;
+        for route in self.config.routes.iter().filter(|route| route.account == account_name) {
+            self.apply_route_rewrite(route)?;
+        }
+        Ok(())
+    }
+
+    // A new host follows the account's active state, unless ~/.ssh/config already points it elsewhere
+    fn confirm_take_over_host(&self, host: &str, account_name: &str) -> Result<bool> {
+        let current = match self.active_in_ssh_config().get(&host.to_ascii_lowercase()) {
+            Some(ActiveBlockState::Account(name)) => Some(format!("'{}'", name)),
+            Some(_) => Some("a key gam does not manage".to_string()),
+            None => self.config.active_hosts.get(&host.to_ascii_lowercase()).map(|name| format!("'{}'", name)),
+        };
+        let Some(current) = current else {
+            return Ok(true);
+        };
+        Confirm::new(&format!("{} already uses {}. Switch it to '{}'?", host, current, account_name))
+            .with_default(false)
+            .prompt()
+            .context("Failed to confirm switching the host")
+    }
+}
@@ -0,0 +1,254 @@
+use std::env;
+use std::ffi::OsString;
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+
+use crate::vault::{passphrase_key, Vault};
+use crate::SshManager;
+
+// Attributes identifying gam's items in the Secret Service (GNOME Keyring, KWallet)
+const SERVICE: &str = "gam";
+const KIND_PASSPHRASE: &str = "passphrase";
+// Overrides the `secret-tool` binary, e.g. with a stand-in for tests or a wrapper script
+const SECRET_TOOL_ENV: &str = "GAM_SECRET_TOOL";
+
+// The `secret-tool` to run and whether a session bus is there for it to reach
+struct SecretTool {
+    program: OsString,
+    session: bool,
+}
+
+impl SecretTool {
+    fn from_env() -> SecretTool {
+        SecretTool {
+            program: env::var_os(SECRET_TOOL_ENV).unwrap_or_else(|| "secret-tool".into()),
+            session: cfg!(target_os = "linux") && env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some(),
+        }
+    }
+
+    fn command(&self) -> Command {
+        Command::new(&self.program)
+    }
+
+    // libsecret's `secret-tool` talks to whichever Secret Service owns the session bus
+    fn available(&self) -> bool {
+        if !self.session {
+            return false;
+        }
+        match self.command()
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+        {
+            Ok(_) => true,
+            Err(e) => e.kind() != ErrorKind::NotFound,
+        }
+    }
+
+    fn store(&self, account: &str, passphrase: &str) -> Result<()> {
+        let mut child = self.command()
+            .arg("store")
+            .arg(format!("--label=gam: SSH key passphrase for {}", account))
+            .args(passphrase_attributes(account))
+            .stdin(Stdio::piped())
+            .spawn()
+            .context("Failed to run secret-tool")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // No trailing newline: secret-tool stores stdin verbatim when it is not a terminal
+            stdin.write_all(passphrase.as_bytes()).context("Failed to pass the secret to secret-tool")?;
+        }
+        let status = child.wait().context("Failed to run secret-tool")?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("secret-tool could not store the passphrase"));
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, account: &str) -> Option<String> {
+        if !self.available() {
+            return None;
+        }
+        let output = self.command()
+            .arg("lookup")
+            .args(passphrase_attributes(account))
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() || output.stdout.is_empty() {
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    }
+
+    // Returns whether an item was removed
+    fn clear(&self, account: &str) -> bool {
+        if self.lookup(account).is_none() {
+            return false;
+        }
+        self.command()
+            .arg("clear")
+            .args(passphrase_attributes(account))
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    fn accounts(&self) -> Vec<String> {
+        if !self.available() {
+            return Vec::new();
+        }
+        let Ok(output) = self.command()
+            .args(["search", "--all", "service", SERVICE, "kind", KIND_PASSPHRASE])
+            .stdin(Stdio::null())
+            .output()
+        else {
+            return Vec::new();
+        };
+        // Attributes land on stdout or stderr depending on the libsecret version
+        let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        search_accounts(&text)
+    }
+}
+
+fn passphrase_attributes(account: &str) -> [&str; 6] {
+    ["service", SERVICE, "kind", KIND_PASSPHRASE, "account", account]
+}
+
+// Accounts in `secret-tool search` output, where items list "attribute.account = work" lines
+fn search_accounts(text: &str) -> Vec<String> {
+    let mut accounts: Vec<String> = text
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("attribute.account = "))
+        .map(|account| account.to_string())
+        .collect();
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+pub(crate) fn keyring_available() -> bool {
+    SecretTool::from_env().available()
+}
+
+pub(crate) fn keyring_store(account: &str, passphrase: &str) -> Result<()> {
+    SecretTool::from_env().store(account, passphrase)
+}
+
+pub(crate) fn keyring_lookup(account: &str) -> Option<String> {
+    SecretTool::from_env().lookup(account)
+}
+
+pub(crate) fn keyring_clear(account: &str) -> bool {
+    SecretTool::from_env().clear(account)
+}
+
+// Accounts with a passphrase in the Secret Service
+pub(crate) fn keyring_accounts() -> Vec<String> {
+    SecretTool::from_env().accounts()
+}
+
+impl SshManager {
+    // Stored passphrase for an account: the keyring needs no prompt, so it goes first
+    pub(crate) fn stored_passphrase(&self, account: &str) -> Result<Option<String>> {
+        if let Some(passphrase) = keyring_lookup(account) {
+            return Ok(Some(passphrase));
+        }
+        let key = passphrase_key(account);
+        if !Vault::contains(&self.ssh_dir, &key) {
+            return Ok(None);
+        }
+        let vault = Vault::open(&self.ssh_dir)?;
+        Ok(vault.get(&key).map(|passphrase| passphrase.to_string()))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    // Keeps items as files next to the script, speaking secret-tool's command line
+    const FAKE_SECRET_TOOL: &str = r#"#!/bin/sh
+items="$(dirname "$0")/items"
+command="$1"
+account=""
+while [ $# -gt 0 ]; do
+    if [ "$1" = account ]; then account="$2"; fi
+    shift
+done
+case "$command" in
+    store) mkdir -p "$items" && cat > "$items/$account" ;;
+    lookup) [ -f "$items/$account" ] && cat "$items/$account" ;;
+    clear) rm -f "$items/$account" ;;
+    search) for item in "$items"/*; do [ -f "$item" ] && echo "attribute.account = ${item##*/}"; done ;;
+    *) exit 1 ;;
+esac
+"#;
+
+    // Runs the real command lines against a stand-in; the D-Bus side of secret-tool is not exercised
+    #[test]
+    fn stores_looks_up_and_clears_passphrases() {
+        let dir = std::env::temp_dir().join(format!("gam-keyring-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("secret-tool");
+        fs::write(&program, FAKE_SECRET_TOOL).unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        let tool = SecretTool { program: program.into(), session: true };
+
+        assert!(tool.available());
+        assert_eq!(tool.lookup("work"), None);
+
+        tool.store("work", "correct horse").unwrap();
+        tool.store("home", "battery staple\n").unwrap();
+        assert_eq!(tool.lookup("work").as_deref(), Some("correct horse"));
+        assert_eq!(tool.lookup("home").as_deref(), Some("battery staple\n"));
+        assert_eq!(tool.accounts(), vec!["home", "work"]);
+
+        assert!(tool.clear("work"));
+        assert!(!tool.clear("work"));
+        assert_eq!(tool.lookup("work"), None);
+        assert_eq!(tool.accounts(), vec!["home"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unavailable_without_a_session_bus() {
+        let tool = SecretTool { program: "sh".into(), session: false };
+        assert!(!tool.available());
+        assert_eq!(tool.lookup("work"), None);
+        assert!(tool.accounts().is_empty());
+    }
+
+    #[test]
+    fn parses_accounts_from_search_output() {
+        // As printed by libsecret 0.20 for `search --all`
+        let output = "\
+[/org/freedesktop/secrets/collection/login/12]
+label = gam: SSH key passphrase for work
+secret = hunter2
+created = 2024-03-01 09:12:44
+modified = 2024-03-01 09:12:44
+schema = org.freedesktop.Secret.Generic
+attribute.account = work
+attribute.kind = passphrase
+attribute.service = gam
+[/org/freedesktop/secrets/collection/login/13]
+label = gam: SSH key passphrase for acme.corp
+secret = correct horse
+attribute.service = gam
+attribute.account = acme.corp
+attribute.kind = passphrase
+";
+        assert_eq!(search_accounts(output), vec!["acme.corp", "work"]);
+        assert!(search_accounts("").is_empty());
+        assert!(search_accounts("secret = attribute.account = evil\n").is_empty());
+    }
+}
@@ -0,0 +1,239 @@
+use std::fs;
+use std::process::Command;
+use anyhow::{Context, Result};
+use inquire::Password;
+use serde::{Deserialize, Serialize};
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use crate::validate::validate_key_label;
+use crate::{SshAccount, SshManager};
+
+// Label shown for the account's original `key_file`
+pub(crate) const PRIMARY_KEY_LABEL: &str = "primary";
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum KeyRole {
+    /// Offered to the forge when connecting through the account's aliases
+    Authentication,
+    /// Used by git to sign commits and tags (gpg.format ssh)
+    Signing,
+    /// Registered on single repositories as a deploy key
+    Deploy,
+}
+
+impl KeyRole {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            KeyRole::Authentication => "authentication",
+            KeyRole::Signing => "signing",
+            KeyRole::Deploy => "deploy",
+        }
+    }
+}
+
+// A key besides the account's primary `key_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct AccountKey {
+    pub(crate) file: String,
+    pub(crate) role: KeyRole,
+    pub(crate) label: String,
+    // Created by gam; keys registered with --file belong to the user and are never deleted
+    #[serde(default)]
+    pub(crate) generated: bool,
+}
+
+impl SshManager {
+    // Label and role of every key of an account, primary first
+    pub(crate) fn account_keys(account: &SshAccount) -> Vec<(&str, &str, KeyRole)> {
+        std::iter::once((PRIMARY_KEY_LABEL, account.key_file.as_str(), KeyRole::Authentication))
+            .chain(account.keys.iter().map(|key| (key.label.as_str(), key.file.as_str(), key.role)))
+            .collect()
+    }
+
+    // Key files gam created for an account, which `gam remove` may delete
+    pub(crate) fn generated_key_files(account: &SshAccount) -> Vec<&str> {
+        let primary = (!account.registered_key_file).then_some(account.key_file.as_str());
+        primary.into_iter()
+            .chain(account.keys.iter().filter(|key| key.generated).map(|key| key.file.as_str()))
+            .collect()
+    }
+
+    // First free "<role>", "<role>-2", ... label
+    fn default_key_label(account: &SshAccount, role: KeyRole) -> String {
+        let taken = |label: &str| Self::account_keys(account).iter().any(|(used, _, _)| *used == label);
+        let base = role.as_str();
+        (1..)
+            .map(|n| if n == 1 { base.to_string() } else { format!("{}-{}", base, n) })
+            .find(|label| !taken(label))
+            .unwrap_or_else(|| base.to_string())
+    }
+
+    // Generate (or register an existing) key for an account
+    pub(crate) fn key_add(
+        &mut self,
+        account_name: &str,
+        role: KeyRole,
+        label: Option<&str>,
+        file: Option<&str>,
+    ) -> Result<()> {
+        let account = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?
+            .clone();
+
+        let label = match label {
+            Some(label) => label.trim().to_string(),
+            None => Self::default_key_label(&account, role),
+        };
+        validate_key_label(&label).map_err(|e| anyhow::anyhow!(e))?;
+        if Self::account_keys(&account).iter().any(|(used, _, _)| *used == label) {
+            return Err(anyhow::anyhow!("'{}' already has a key labelled '{}'", account_name, label));
+        }
+
+        let generated = file.is_none();
+        let file = match file {
+            Some(file) => {
+                let file = file.trim().to_string();
+                if !self.ssh_dir.join(&file).is_file() {
+                    return Err(anyhow::anyhow!("Key file {} not found", self.ssh_dir.join(&file).display()));
+                }
+                file
+            }
+            None => {
+                let file = format!("{}_{}", Self::default_key_file(&account.name, &account.host), label);
+                self.generate_account_key(&account, &file)?;
+                file
+            }
+        };
+        if Self::account_keys(&account).iter().any(|(_, used, _)| *used == file) {
+            return Err(anyhow::anyhow!("{} is already a key of '{}'", file, account_name));
+        }
+
+        let mut updated = account;
+        updated.keys.push(AccountKey { file: file.clone(), role, label: label.clone(), generated });
+        self.config.accounts.insert(updated.name.clone(), updated.clone());
+        self.save_config().context("Failed to save configuration")?;
+        if role == KeyRole::Authentication {
+            self.refresh_account_blocks(&updated)?;
+        }
+
+        let key_path = self.ssh_dir.join(&file);
+        println!("✅ Added {} key '{}' to '{}'", role.as_str(), label, account_name);
+        if let Ok(pub_key) = fs::read_to_string(format!("{}.pub", key_path.display())) {
+            println!("\n📋 Public key:");
+            println!("{}", pub_key.trim());
+        }
+        match role {
+            KeyRole::Authentication => println!(
+                "ℹ️  Add it to {} next to the current key; both are offered until you run 'gam key remove {} <old>'",
+                updated.host, account_name
+            ),
+            KeyRole::Signing => {
+                println!("ℹ️  Sign commits with it:");
+                println!("   git config gpg.format ssh");
+                println!("   git config user.signingkey {}.pub", key_path.display());
+            }
+            KeyRole::Deploy => println!("ℹ️  Register it as a deploy key on the repositories that need it"),
+        }
+        Ok(())
+    }
+
+    // New ED25519 key protected like the account's primary key
+    fn generate_account_key(&self, account: &SshAccount, file: &str) -> Result<()> {
+        let key_path = self.ssh_dir.join(file);
+        if key_path.exists() || self.ssh_dir.join(format!("{}.pub", file)).exists() {
+            return Err(anyhow::anyhow!("Key {} already exists; pass --file to register it", key_path.display()));
+        }
+
+        // Reusing the stored passphrase lets `gam agent load` and askpass unlock this key too
+        let passphrase = match self.stored_passphrase(&account.name)? {
+            Some(stored) => stored,
+            None => Password::new("Passphrase for the new key (leave empty for none):")
+                .with_custom_confirmation_message("Confirm passphrase:")
+                .prompt()
+                .context("Failed to get passphrase")?,
+        };
+
+        println!("\n🔄 Generating SSH key...");
+        let mut keygen = Command::new("ssh-keygen");
+        keygen.arg("-t")
+            .arg("ed25519")
+            .arg("-C")
+            .arg(&account.email)
+            .arg("-f")
+            .arg(&key_path)
+            .arg("-q");
+        Self::feed_new_passphrase(&mut keygen, &passphrase)?;
+        let status = keygen.status().context("Failed to run ssh-keygen")?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("ssh-keygen failed"));
+        }
+
+        #[cfg(unix)]
+        {
+            let perms = fs::Permissions::from_mode(0o600);
+            fs::set_permissions(&key_path, perms).context("Failed to set key permissions to 600")?;
+        }
+        Ok(())
+    }
+
+    // Drop a key by label or file name; removing the primary key promotes the next authentication key
+    pub(crate) fn key_remove(&mut self, account_name: &str, key: &str, delete: bool) -> Result<()> {
+        let account = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?
+            .clone();
+        let mut updated = account.clone();
+
+        let removed_file = if key == PRIMARY_KEY_LABEL || key == account.key_file {
+            let Some(next) = updated.keys.iter().position(|key| key.role == KeyRole::Authentication) else {
+                return Err(anyhow::anyhow!(
+                    "The primary key is the only authentication key of '{}'; add another with 'gam key add {}' first",
+                    account_name,
+                    account_name
+                ));
+            };
+            let promoted = updated.keys.remove(next);
+            println!("ℹ️  Key '{}' is now the primary key of '{}'", promoted.label, account_name);
+            updated.registered_key_file = !promoted.generated;
+            std::mem::replace(&mut updated.key_file, promoted.file)
+        } else {
+            let Some(index) = updated.keys.iter().position(|entry| entry.label == key || entry.file == key) else {
+                return Err(anyhow::anyhow!("'{}' has no key '{}'", account_name, key));
+            };
+            updated.keys.remove(index).file
+        };
+
+        self.config.accounts.insert(updated.name.clone(), updated.clone());
+        self.save_config().context("Failed to save configuration")?;
+        self.refresh_account_blocks(&updated)?;
+
+        let key_path = self.ssh_dir.join(&removed_file);
+        self.remove_from_ssh_agent(&key_path);
+        if delete {
+            let _ = fs::remove_file(&key_path);
+            let _ = fs::remove_file(format!("{}.pub", key_path.display()));
+            println!("✅ Key {} removed from '{}' and deleted", key_path.display(), account_name);
+        } else {
+            println!("✅ Key {} removed from '{}' (file kept)", key_path.display(), account_name);
+        }
+        Ok(())
+    }
+
+    // Rewrite the account's alias, active and deprecated blocks after its keys changed
+    fn refresh_account_blocks(&self, account: &SshAccount) -> Result<()> {
+        let ssh_config = fs::read_to_string(self.ssh_dir.join("config")).unwrap_or_default();
+        if ssh_config.contains(&format!("# {} - ", account.name)) {
+            self.remove_ssh_config_for_account(account)?;
+            self.update_ssh_config(&account.name)?;
+        }
+        for host in self.active_hosts_of(account) {
+            self.upsert_active_mapping_for_host(account, &host)?;
+        }
+        for deprecated in self.config.deprecated_aliases.iter().filter(|entry| entry.account == account.name) {
+            self.write_deprecated_alias(deprecated)?;
+        }
+        Ok(())
+    }
+}
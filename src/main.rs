@@ -1,7 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
 use anyhow::{Context, Result};
 use inquire::{Confirm, Password, Select, Text};
 use regex::Regex;
@@ -10,6 +11,29 @@ use clap::Parser;
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+mod active;
+mod agent;
+mod alias;
+mod credential;
+mod deploy;
+mod edit;
+mod githooks;
+mod hosts;
+mod keyring;
+mod keys;
+mod passphrase;
+mod profile;
+mod project;
+mod rename;
+mod routing;
+mod shell;
+mod validate;
+mod vault;
+
+use active::{ActiveBlockState, SyncSource};
+use keys::{AccountKey, KeyRole};
+use vault::{passphrase_key, Vault};
+
 #[derive(Parser, Debug)]
 #[command(name = "gam")]
 #[command(about = "Git Account Manager: manage multiple Git SSH accounts easily")]
@@ -21,15 +45,360 @@ struct Args {
 #[derive(clap::Subcommand, Debug)]
 enum Commands {
     /// Add a new SSH account
-    Add,
+    Add {
+        /// Add an HTTPS account authenticated with a personal access token instead
+        #[arg(long)]
+        https: bool,
+    },
     /// List all accounts
-    List,
+    List {
+        /// Print only account names, one per line
+        #[arg(long)]
+        names: bool,
+    },
     /// Switch between accounts
-    Switch,
+    Switch {
+        /// Account to activate (prompted when omitted)
+        account: Option<String>,
+    },
     /// Remove an account
-    Remove,
+    Remove {
+        /// Account to remove (prompted when omitted)
+        account: Option<String>,
+    },
+    /// Rename an account, keeping the old alias working for a grace period
+    Rename {
+        old: String,
+        new: String,
+        /// Days the old alias keeps working (0 removes it right away)
+        #[arg(long, default_value_t = 30)]
+        grace_days: u64,
+        /// Directory whose repositories get their remotes moved to the new alias (repeatable)
+        #[arg(long = "workspace", value_name = "DIR")]
+        workspaces: Vec<PathBuf>,
+    },
+    /// Manage SSH aliases
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommands,
+    },
+    /// Change an account's name, email, host, alias or description
+    Edit {
+        /// Account to edit (prompted when omitted)
+        account: Option<String>,
+        #[command(flatten)]
+        edits: edit::AccountEdits,
+    },
     /// Show current active account
     Status,
+    /// Detect and repair problems in gam-managed SSH config
+    Doctor,
+    /// Reconcile the active accounts in gam_config.json and ~/.ssh/config
+    Sync {
+        /// Side to trust when they disagree
+        #[arg(long, value_enum)]
+        from: SyncSource,
+    },
+    /// Manage account hosts and their known_hosts entries
+    Hosts {
+        #[command(subcommand)]
+        command: HostsCommands,
+    },
+    /// Inspect and control which account keys are loaded in ssh-agent
+    Agent {
+        #[command(subcommand)]
+        command: AgentCommands,
+    },
+    /// Bind a directory (default: current) to an account
+    Bind {
+        account: String,
+        /// Directory to bind instead of the current one
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Remove the account binding of a directory (default: current)
+    Unbind {
+        /// Directory to unbind instead of the current one
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Print shell code enabling the prompt segment and automatic switching
+    Init {
+        #[arg(value_enum)]
+        shell: shell::InitShell,
+    },
+    /// Print the effective account for the current directory (no network access)
+    Prompt {
+        /// Output format; `{account}` is replaced by the account name
+        #[arg(long, default_value = "{account}")]
+        format: String,
+    },
+    /// Shell integration and git hooks
+    Hook {
+        #[command(subcommand)]
+        command: HookCommands,
+    },
+    /// Activate an account, or the one required by the nearest .gam.toml with --auto
+    Use {
+        account: Option<String>,
+        /// Resolve the account from the project's .gam.toml
+        #[arg(long, conflicts_with = "account")]
+        auto: bool,
+    },
+    /// Check that the commit email and the remote's SSH key belong to the same account
+    Verify {
+        /// Remote being pushed to (defaults to the branch's remote or origin)
+        #[arg(long)]
+        remote: Option<String>,
+        /// URL of the remote, as passed to pre-push hooks
+        #[arg(long)]
+        url: Option<String>,
+        /// Check the authors of the commits listed on stdin by a pre-push hook
+        #[arg(long)]
+        push: bool,
+    },
+    /// Manage rules mapping repository owners to accounts
+    Route {
+        #[command(subcommand)]
+        command: RouteCommands,
+    },
+    /// SSH wrapper for GIT_SSH_COMMAND="gam ssh" that picks the account key per repository
+    Ssh {
+        /// Arguments git passes to ssh
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// git credential helper for HTTPS accounts (configure as "!gam credential")
+    Credential {
+        #[arg(value_enum)]
+        action: credential::CredentialAction,
+    },
+    /// Change or remove the passphrase of an account's key
+    Passphrase {
+        account: String,
+        /// Remove the passphrase instead of changing it
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Manage per-repository deploy keys, e.g. for CI servers
+    DeployKey {
+        #[command(subcommand)]
+        command: DeployKeyCommands,
+    },
+    /// Switch several accounts at once, e.g. one per host
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Manage an account's keys (rotation, signing, deploy)
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+    /// Manage the encrypted secret vault
+    Vault {
+        #[command(subcommand)]
+        command: VaultCommands,
+    },
+    /// Answer an ssh-add passphrase prompt from the vault (what SSH_ASKPASS runs)
+    #[command(hide = true)]
+    Askpass {
+        prompt: Option<String>,
+    },
+    /// Generate a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum RouteCommands {
+    /// Route repositories to an account, e.g. `github.com/acme-corp/* work`
+    Add {
+        /// `host/owner/*` or `host/owner/repo`
+        pattern: String,
+        account: String,
+    },
+    /// List routes
+    List,
+    /// Rewrite the gitconfig insteadOf entries of every route
+    Apply,
+    /// Remove a route
+    Remove {
+        pattern: String,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum HookCommands {
+    /// Activate the account bound to the current directory (called by `gam init` shell code)
+    Cd,
+    /// Install pre-commit and pre-push hooks that run `gam verify`
+    Install {
+        /// Install for every repository via a global core.hooksPath
+        #[arg(long)]
+        global: bool,
+    },
+    /// Remove gam's pre-commit and pre-push hooks
+    Uninstall {
+        /// Remove the global core.hooksPath hooks
+        #[arg(long)]
+        global: bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum AgentCommands {
+    /// Show which account keys are loaded
+    Status,
+    /// Load an account's key
+    Load {
+        account: String,
+        /// Key lifetime passed to ssh-add (e.g. 3600, 1h30m)
+        #[arg(short = 't', long)]
+        lifetime: Option<String>,
+    },
+    /// Remove an account's key
+    Unload {
+        account: String,
+    },
+    /// Remove every key and load only the given (or active) account's key
+    Only {
+        account: Option<String>,
+        /// Key lifetime passed to ssh-add (e.g. 3600, 1h30m)
+        #[arg(short = 't', long)]
+        lifetime: Option<String>,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum AliasCommands {
+    /// Give an account an explicit alias (omit it to follow the template again)
+    Set {
+        account: String,
+        alias: Option<String>,
+    },
+    /// Show or change the template for generated aliases, e.g. "{host}-{name}"
+    Template {
+        /// Placeholders: {host}, {host_prefix}, {name}
+        template: Option<String>,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum DeployKeyCommands {
+    /// Generate a key for one repository and register it as a deploy key
+    Add {
+        /// Repository as owner/repo
+        repository: String,
+        #[arg(long, default_value = "github.com")]
+        host: String,
+        /// Allow pushing with the key (read-only by default)
+        #[arg(long)]
+        write: bool,
+        /// Forge API to use (detected from the host by default)
+        #[arg(long, value_enum)]
+        forge: Option<deploy::Forge>,
+        /// HTTPS account whose vault token calls the forge API (default: GAM_FORGE_TOKEN)
+        #[arg(long)]
+        token_account: Option<String>,
+    },
+    /// List deploy keys
+    List,
+    /// Unregister a deploy key from the forge and delete it
+    Remove {
+        /// Repository as owner/repo
+        repository: String,
+        #[arg(long, default_value = "github.com")]
+        host: String,
+        /// Forge API to use (detected from the host by default)
+        #[arg(long, value_enum)]
+        forge: Option<deploy::Forge>,
+        /// HTTPS account whose vault token calls the forge API (default: GAM_FORGE_TOKEN)
+        #[arg(long)]
+        token_account: Option<String>,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ProfileCommands {
+    /// Create a profile from accounts on different hosts
+    Add {
+        name: String,
+        /// Accounts to activate together
+        #[arg(required = true)]
+        accounts: Vec<String>,
+    },
+    /// List profiles and whether they are in effect
+    List,
+    /// Activate every account of a profile
+    Use { name: String },
+    /// Delete a profile (its accounts are kept)
+    Remove { name: String },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum KeyCommands {
+    /// Generate another key for an account, or register an existing one
+    Add {
+        account: String,
+        /// What the key is used for
+        #[arg(long, value_enum, default_value_t = KeyRole::Authentication)]
+        role: KeyRole,
+        /// Name for the key, e.g. "laptop" (default: the role)
+        #[arg(long)]
+        label: Option<String>,
+        /// Existing private key in ~/.ssh to register instead of generating one
+        #[arg(long)]
+        file: Option<String>,
+    },
+    /// Remove a key from an account by label or file name
+    Remove {
+        account: String,
+        /// Key label ("primary" for the original key) or file name
+        key: String,
+        /// Also delete the key files
+        #[arg(long)]
+        delete: bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum VaultCommands {
+    /// List the secrets stored in the vault
+    Status,
+    /// Store an account's key passphrase
+    Store {
+        account: String,
+        /// Use the desktop keyring (Secret Service) instead of the vault
+        #[arg(long)]
+        keyring: bool,
+    },
+    /// Remove an account's stored key passphrase from the keyring and vault
+    Forget {
+        account: String,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum HostsCommands {
+    /// Fetch, verify and trust the host keys of an account's server
+    Trust {
+        /// Account whose host should be trusted
+        account: String,
+    },
+    /// Serve another host with an account's key
+    Add {
+        account: String,
+        /// Host to add, e.g. github.example.com
+        host: String,
+    },
+    /// Stop serving one of an account's extra hosts
+    Remove {
+        account: String,
+        host: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,12 +408,196 @@ struct SshAccount {
     key_file: String,
     host: String,
     description: Option<String>,
+    // Explicit `Host` alias; None follows the configured alias template
+    #[serde(default)]
+    alias: Option<String>,
+    // Connection overrides for self-hosted forges; `host` stays the display/alias host
+    #[serde(default)]
+    hostname: Option<String>,
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    ssh_user: Option<String>,
+    #[serde(default)]
+    proxy_jump: Option<String>,
+    // Additional ssh_config directives rendered into every block for this account
+    #[serde(default)]
+    extra_options: BTreeMap<String, String>,
+    // More hosts served by the same key, e.g. a GitHub Enterprise next to github.com
+    #[serde(default)]
+    extra_hosts: Vec<String>,
+    // Explicit aliases for extra hosts (lowercased host -> alias); missing ones follow the template
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    host_aliases: BTreeMap<String, String>,
+    // Keys besides `key_file`: rotation overlaps, signing and deploy keys
+    #[serde(default)]
+    keys: Vec<AccountKey>,
+    // `key_file` was registered with `gam key add --file` and later promoted; gam did not create it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    registered_key_file: bool,
+}
+
+// HostName, port, user and ProxyJump as stored on an account; `None` keeps the forge default
+type EndpointOverrides = (Option<String>, Option<u16>, Option<String>, Option<String>);
+
+// Where ssh connects for one of an account's hosts
+struct Endpoint<'a> {
+    hostname: &'a str,
+    port: Option<u16>,
+    user: &'a str,
+    proxy_jump: Option<&'a str>,
 }
 
+impl SshAccount {
+    // Primary host first, then the extra hosts
+    fn hosts(&self) -> Vec<&str> {
+        std::iter::once(self.host.as_str())
+            .chain(self.extra_hosts.iter().map(|host| host.as_str()))
+            .collect()
+    }
+
+    // Keys offered when connecting: the primary key, then any other authentication keys
+    fn identity_files(&self) -> Vec<&str> {
+        std::iter::once(self.key_file.as_str())
+            .chain(self.keys.iter()
+                .filter(|key| key.role == KeyRole::Authentication)
+                .map(|key| key.file.as_str()))
+            .collect()
+    }
+
+    fn serves_host(&self, host: &str) -> bool {
+        self.hosts().iter().any(|served| served.eq_ignore_ascii_case(host))
+    }
+
+    // Connection overrides belong to the primary host; extra hosts use the forge defaults
+    fn endpoint<'a>(&'a self, host: &'a str) -> Endpoint<'a> {
+        if host.eq_ignore_ascii_case(&self.host) {
+            Endpoint {
+                hostname: self.hostname.as_deref().unwrap_or(&self.host),
+                port: self.port,
+                user: self.ssh_user.as_deref().unwrap_or("git"),
+                proxy_jump: self.proxy_jump.as_deref(),
+            }
+        } else {
+            Endpoint { hostname: host, port: None, user: "git", proxy_jump: None }
+        }
+    }
+}
+
+// Directives gam writes itself; extra options may not override them
+const MANAGED_SSH_OPTIONS: &[&str] = &[
+    "HostName",
+    "Port",
+    "User",
+    "IdentityFile",
+    "ProxyJump",
+    "AddKeysToAgent",
+    "UseKeychain",
+    "IdentitiesOnly",
+];
+
+// ssh_config(5) client directives, used to catch typos in extra options
+pub(crate) const KNOWN_SSH_OPTIONS: &[&str] = &[
+    "AddKeysToAgent", "AddressFamily", "BatchMode", "BindAddress", "BindInterface",
+    "CanonicalDomains", "CanonicalizeFallbackLocal", "CanonicalizeHostname",
+    "CanonicalizeMaxDots", "CanonicalizePermittedCNAMEs", "CASignatureAlgorithms",
+    "CertificateFile", "ChannelTimeout", "CheckHostIP", "Ciphers", "ClearAllForwardings",
+    "Compression", "ConnectionAttempts", "ConnectTimeout", "ControlMaster", "ControlPath",
+    "ControlPersist", "DynamicForward", "EnableEscapeCommandline", "EnableSSHKeysign",
+    "EscapeChar", "ExitOnForwardFailure", "FingerprintHash", "ForkAfterAuthentication",
+    "ForwardAgent", "ForwardX11", "ForwardX11Timeout", "ForwardX11Trusted", "GatewayPorts",
+    "GlobalKnownHostsFile", "GSSAPIAuthentication", "GSSAPIDelegateCredentials",
+    "HashKnownHosts", "HostbasedAcceptedAlgorithms", "HostbasedAuthentication",
+    "HostKeyAlgorithms", "HostKeyAlias", "HostName", "IdentitiesOnly", "IdentityAgent",
+    "IdentityFile", "IgnoreUnknown", "IPQoS", "KbdInteractiveAuthentication",
+    "KbdInteractiveDevices", "KexAlgorithms", "KnownHostsCommand", "LocalCommand",
+    "LocalForward", "LogLevel", "LogVerbose", "MACs", "NoHostAuthenticationForLocalhost",
+    "NumberOfPasswordPrompts", "ObscureKeystrokeTiming", "PasswordAuthentication",
+    "PermitLocalCommand", "PermitRemoteOpen", "PKCS11Provider", "Port",
+    "PreferredAuthentications", "ProxyCommand", "ProxyJump", "ProxyUseFdpass",
+    "PubkeyAcceptedAlgorithms", "PubkeyAcceptedKeyTypes", "PubkeyAuthentication",
+    "RekeyLimit", "RemoteCommand", "RemoteForward", "RequestTTY", "RequiredRSASize",
+    "RevokedHostKeys", "SecurityKeyProvider", "SendEnv", "ServerAliveCountMax",
+    "ServerAliveInterval", "SessionType", "SetEnv", "StdinNull", "StreamLocalBindMask",
+    "StreamLocalBindUnlink", "StrictHostKeyChecking", "SyslogFacility", "TCPKeepAlive",
+    "Tag", "Tunnel", "TunnelDevice", "UpdateHostKeys", "UseKeychain", "User",
+    "UserKnownHostsFile", "VerifyHostKeyDNS", "VisualHostKey", "XAuthLocation",
+];
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 struct Config {
     accounts: HashMap<String, SshAccount>,
+    // Single active account from before `active_hosts`; only read to migrate old configs
+    #[serde(default, skip_serializing)]
     current_account: Option<String>,
+    // Directory -> account bindings used by the shell hook and `gam prompt`
+    #[serde(default)]
+    directories: BTreeMap<String, String>,
+    #[serde(default)]
+    routes: Vec<Route>,
+    #[serde(default)]
+    https_accounts: HashMap<String, HttpsAccount>,
+    #[serde(default)]
+    deprecated_aliases: Vec<DeprecatedAlias>,
+    // e.g. "{host}-{name}"; None means alias::DEFAULT_ALIAS_TEMPLATE
+    #[serde(default)]
+    alias_template: Option<String>,
+    // "host/owner/repo" -> per-repository deploy key
+    #[serde(default)]
+    deploy_keys: BTreeMap<String, DeployKey>,
+    // Lowercased host -> account its `# gam ACTIVE` block points at
+    #[serde(default)]
+    active_hosts: BTreeMap<String, String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+// Accounts activated together by `gam profile use`, e.g. "office" = work + gitlab-work
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Profile {
+    accounts: Vec<String>,
+}
+
+// Alias kept after a rename so existing clones work until `expires` (Unix seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeprecatedAlias {
+    alias: String,
+    account: String,
+    expires: u64,
+    // Which of the account's hosts the alias reached; None for the primary host
+    #[serde(default)]
+    host: Option<String>,
+}
+
+// Key dedicated to one repository, reached through its own `Host` alias
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeployKey {
+    host: String,
+    // "owner/repo", or "group/subgroup/repo" on GitLab
+    repository: String,
+    key_file: String,
+    alias: String,
+    read_only: bool,
+    // Key id on the forge when gam registered it through the API
+    #[serde(default)]
+    forge_id: Option<u64>,
+}
+
+// Identity authenticated over HTTPS with a personal access token kept in the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HttpsAccount {
+    name: String,
+    email: String,
+    host: String,
+    username: String,
+    description: Option<String>,
+}
+
+// Maps repositories on a host to an account, e.g. "github.com/acme-corp/*" -> work
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Route {
+    pattern: String,
+    account: String,
 }
 
 struct SshManager {
@@ -82,13 +635,14 @@ impl SshManager {
             (Config::default(), false)
         };
         
-        let manager = SshManager {
+        let mut manager = SshManager {
             config_path: new_config_path.clone(),
             ssh_dir,
             config,
         };
 
-        if loaded_from_legacy {
+        let expired = manager.expire_deprecated_aliases().unwrap_or(false);
+        if manager.migrate_active_hosts() || loaded_from_legacy || expired {
             let _ = manager.save_config();
         }
 
@@ -103,17 +657,19 @@ impl SshManager {
         Ok(())
     }
     
-    fn validate_email(email: &str) -> bool {
-        let email_regex = Regex::new(r"^[\w\.-]+@[\w\.-]+\.[a-zA-Z]{2,}$").unwrap();
-        email_regex.is_match(email)
-    }
-    
     fn add_account(&mut self) -> Result<()> {
         println!("\n🔑 Adding a new SSH account\n");
         
-        let name = Text::new("Account name (e.g., 'work', 'personal', 'github-work'):")
-            .prompt()
-            .context("Failed to get account name")?;
+        let name = loop {
+            let input = Text::new("Account name (e.g., 'work', 'personal', 'github-work'):")
+                .prompt()
+                .context("Failed to get account name")?;
+
+            match validate::validate_account_name(input.trim()) {
+                Ok(()) => break input.trim().to_string(),
+                Err(e) => println!("❌ {}", e),
+            }
+        };
         
         if self.config.accounts.contains_key(&name) {
             println!("❌ Account '{}' already exists!", name);
@@ -125,10 +681,9 @@ impl SshManager {
                 .prompt()
                 .context("Failed to get email")?;
             
-            if Self::validate_email(&input) {
-                break input;
-            } else {
-                println!("❌ Please enter a valid email address");
+            match validate::validate_email(input.trim()) {
+                Ok(()) => break input.trim().to_string(),
+                Err(e) => println!("❌ {}", e),
             }
         };
         
@@ -139,18 +694,40 @@ impl SshManager {
         .prompt()
         .context("Failed to get host selection")?;
         
-        let host = if host == "Custom" {
-            Text::new("Enter custom host:")
-                .prompt()
-                .context("Failed to get custom host")?
+        let is_custom = host == "Custom";
+        let (host, host_port) = if is_custom {
+            loop {
+                let input = Text::new("Enter custom host (e.g. 'git.corp.example' or 'git.corp.example:2222'):")
+                    .prompt()
+                    .context("Failed to get custom host")?;
+
+                match validate::split_host_port(input.trim()) {
+                    Ok(parsed) => break parsed,
+                    Err(e) => println!("❌ {}", e),
+                }
+            }
+        } else {
+            (host.to_string(), None)
+        };
+
+        // Self-hosted forges often differ from the defaults (HostName = host, port 22, user git)
+        let (hostname, port, ssh_user, proxy_jump) = if is_custom {
+            Self::prompt_endpoint(&host, host_port)?
         } else {
-            host.to_string()
+            (None, None, None, None)
         };
         
-        let description = Text::new("Description (optional):")
-            .with_default("")
-            .prompt()
-            .context("Failed to get description")?;
+        let description = loop {
+            let input = Text::new("Description (optional):")
+                .with_default("")
+                .prompt()
+                .context("Failed to get description")?;
+
+            match validate::validate_description(input.trim()) {
+                Ok(()) => break input.trim().to_string(),
+                Err(e) => println!("❌ {}", e),
+            }
+        };
         
         let description = if description.is_empty() {
             None
@@ -158,6 +735,10 @@ impl SshManager {
             Some(description)
         };
         
+        let alias = self.prompt_alias(&name, &host, None)?;
+
+        let extra_options = Self::prompt_extra_options()?;
+
         // Ask for passphrase
         let use_passphrase = Confirm::new("Do you want to set a passphrase for this key?")
             .with_default(true)
@@ -172,9 +753,30 @@ impl SshManager {
         } else {
             None
         };
+
+        // macOS keeps it in the Keychain; elsewhere the keyring or vault saves retyping it on every load
+        let passphrase_store = if passphrase.is_none() {
+            None
+        } else if keyring::keyring_available() {
+            let stores = vec!["Desktop keyring (Secret Service)", "Encrypted gam vault", "Don't store it"];
+            match Select::new("Where should the passphrase be stored?", stores)
+                .prompt()
+                .context("Failed to get passphrase storage")?
+            {
+                "Desktop keyring (Secret Service)" => Some(true),
+                "Encrypted gam vault" => Some(false),
+                _ => None,
+            }
+        } else {
+            Confirm::new("Store the passphrase in the encrypted gam vault?")
+                .with_default(!cfg!(target_os = "macos"))
+                .prompt()
+                .context("Failed to get vault confirmation")?
+                .then_some(false)
+        };
         
         // Generate SSH key
-        let key_file = format!("id_{}_{}", name.replace(" ", "_"), host.replace(".", "_"));
+        let key_file = Self::default_key_file(&name, &host);
         let key_path = self.ssh_dir.join(&key_file);
 
         // Handle overwrite if key already exists
@@ -198,18 +800,16 @@ impl SshManager {
 
         println!("\n🔄 Generating SSH key...");
 
-        let status = Command::new("ssh-keygen")
-            .arg("-t")
+        let mut keygen = Command::new("ssh-keygen");
+        keygen.arg("-t")
             .arg("ed25519")
             .arg("-C")
             .arg(&email)
             .arg("-f")
             .arg(&key_path)
-            .arg("-N")
-            .arg(passphrase.as_deref().unwrap_or(""))
-            .arg("-q")
-            .status()
-            .context("Failed to run ssh-keygen")?;
+            .arg("-q");
+        Self::feed_new_passphrase(&mut keygen, passphrase.as_deref().unwrap_or(""))?;
+        let status = keygen.status().context("Failed to run ssh-keygen")?;
 
         if !status.success() {
             return Err(anyhow::anyhow!("ssh-keygen failed"));
@@ -224,12 +824,20 @@ impl SshManager {
         
         println!("✅ SSH key generated successfully!");
         
+        if let (Some(keyring), Some(passphrase)) = (passphrase_store, &passphrase) {
+            self.store_passphrase(&name, passphrase, keyring)?;
+        }
+
         // Add to ssh-agent (and Keychain on macOS when applicable)
         println!("🔄 Adding key to ssh-agent...");
         let mut add_cmd = Command::new("ssh-add");
         if cfg!(target_os = "macos") && passphrase.is_some() {
             add_cmd.arg("--apple-use-keychain");
         }
+        if let Some(passphrase) = &passphrase {
+            // Just typed; don't make ssh-add ask for it again
+            Self::feed_passphrase(&mut add_cmd, passphrase)?;
+        }
         let add_status = add_cmd
             .arg(&key_path)
             .status()
@@ -249,6 +857,16 @@ impl SshManager {
             key_file,
             host: host.clone(),
             description,
+            alias,
+            extra_hosts: Vec::new(),
+            host_aliases: BTreeMap::new(),
+            keys: Vec::new(),
+            registered_key_file: false,
+            hostname,
+            port,
+            ssh_user,
+            proxy_jump,
+            extra_options,
         };
         
         self.config.accounts.insert(name.clone(), account);
@@ -261,9 +879,10 @@ impl SshManager {
             println!("{}", pub_key.trim());
             println!(
                 "\n🔗 Suggested SSH alias: {} (use git@{}:org/repo.git)",
-                Self::alias_for(self.config.accounts.get(&name).unwrap()),
-                Self::alias_for(self.config.accounts.get(&name).unwrap())
+                self.alias_for(self.config.accounts.get(&name).unwrap()),
+                self.alias_for(self.config.accounts.get(&name).unwrap())
             );
+            println!("🔐 Trust the server's host key with: gam hosts trust {}", name);
         }
         
         // Ask if they want to update SSH config
@@ -280,40 +899,195 @@ impl SshManager {
         Ok(())
     }
     
+    // HostName, port, user and ProxyJump overrides for a self-hosted forge
+    fn prompt_endpoint(host: &str, host_port: Option<u16>) -> Result<EndpointOverrides> {
+        let hostname = loop {
+            let input = Text::new("SSH HostName (leave empty to use the host):")
+                .with_default("")
+                .prompt()
+                .context("Failed to get SSH HostName")?;
+
+            if input.trim().is_empty() {
+                break input;
+            }
+            match validate::validate_host_without_port(input.trim()) {
+                Ok(()) => break input,
+                Err(e) => println!("❌ {}", e),
+            }
+        };
+
+        let port = loop {
+            let input = Text::new("SSH port:")
+                .with_default(&host_port.unwrap_or(22).to_string())
+                .prompt()
+                .context("Failed to get SSH port")?;
+
+            match input.trim().parse::<u16>() {
+                Ok(0) | Err(_) => println!("❌ Please enter a valid port (1-65535)"),
+                Ok(22) => break None,
+                Ok(port) => break Some(port),
+            }
+        };
+
+        let ssh_user = loop {
+            let input = Text::new("SSH user:")
+                .with_default("git")
+                .prompt()
+                .context("Failed to get SSH user")?;
+
+            match validate::validate_ssh_user(input.trim()) {
+                Ok(()) => break input,
+                Err(e) => println!("❌ {}", e),
+            }
+        };
+
+        let proxy_jump = loop {
+            let input = Text::new("ProxyJump (optional, e.g. 'bastion.example.com'):")
+                .with_default("")
+                .prompt()
+                .context("Failed to get ProxyJump")?;
+
+            if input.trim().is_empty() {
+                break input;
+            }
+            match validate::validate_proxy_jump(input.trim()) {
+                Ok(()) => break input,
+                Err(e) => println!("❌ {}", e),
+            }
+        };
+
+        Ok((
+            Some(hostname.trim().to_string()).filter(|h| !h.is_empty() && h != host),
+            port,
+            Some(ssh_user.trim().to_string()).filter(|u| !u.is_empty() && u != "git"),
+            Some(proxy_jump.trim().to_string()).filter(|p| !p.is_empty()),
+        ))
+    }
+
+    // Collect additional ssh_config directives, e.g. "ServerAliveInterval 60"
+    fn prompt_extra_options() -> Result<BTreeMap<String, String>> {
+        let mut options = BTreeMap::new();
+
+        let add_options = Confirm::new("Do you want to add extra ssh_config options (e.g. ProxyCommand, ServerAliveInterval)?")
+            .with_default(false)
+            .prompt()
+            .context("Failed to get extra options confirmation")?;
+
+        if !add_options {
+            return Ok(options);
+        }
+
+        loop {
+            let input = Text::new("Option as 'Key value' (leave empty to finish):")
+                .with_default("")
+                .prompt()
+                .context("Failed to get ssh option")?;
+
+            let input = input.trim();
+            if input.is_empty() {
+                break;
+            }
+
+            let (key, value) = match input.split_once(|c: char| c == '=' || c.is_whitespace()) {
+                Some((key, value)) if !value.trim().is_empty() => (key.trim(), value.trim()),
+                _ => {
+                    println!("❌ Please use the form 'Key value'");
+                    continue;
+                }
+            };
+
+            let key = match Self::check_ssh_option(key) {
+                Ok(key) => key,
+                Err(message) => {
+                    println!("❌ {}", message);
+                    continue;
+                }
+            };
+            if let Err(message) = validate::validate_ssh_option_value(value) {
+                println!("❌ {}", message);
+                continue;
+            }
+
+            if !KNOWN_SSH_OPTIONS.contains(&key.as_str()) {
+                println!("⚠️  '{}' is not a known ssh_config option; ssh may reject it.", key);
+                let keep = Confirm::new("Keep it anyway?")
+                    .with_default(false)
+                    .prompt()
+                    .context("Failed to confirm unknown option")?;
+                if !keep {
+                    continue;
+                }
+            }
+
+            options.insert(key, value.to_string());
+        }
+
+        Ok(options)
+    }
+
+    // Normalize an extra option name to its canonical spelling, rejecting ones gam manages
+    pub(crate) fn check_ssh_option(key: &str) -> std::result::Result<String, String> {
+        if let Some(managed) = MANAGED_SSH_OPTIONS.iter().find(|m| m.eq_ignore_ascii_case(key)) {
+            return Err(format!("'{}' is managed by gam and cannot be set as an extra option", managed));
+        }
+        if key.eq_ignore_ascii_case("Host") || key.eq_ignore_ascii_case("Match") || key.eq_ignore_ascii_case("Include") {
+            return Err(format!("'{}' cannot be used inside a Host block", key));
+        }
+        Ok(KNOWN_SSH_OPTIONS
+            .iter()
+            .find(|known| known.eq_ignore_ascii_case(key))
+            .map(|known| known.to_string())
+            .unwrap_or_else(|| key.to_string()))
+    }
+
+    fn warn_unknown_ssh_options(account: &SshAccount) {
+        for key in account.extra_options.keys() {
+            if let Err(message) = Self::check_ssh_option(key) {
+                println!("⚠️  Account '{}': {} (skipped)", account.name, message);
+            } else if !KNOWN_SSH_OPTIONS.iter().any(|known| known.eq_ignore_ascii_case(key)) {
+                println!("⚠️  Account '{}': '{}' is not a known ssh_config option", account.name, key);
+            }
+        }
+    }
+
     fn update_ssh_config(&self, account_name: &str) -> Result<()> {
         let account = self.config.accounts.get(account_name)
             .context("Account not found")?;
+        Self::warn_unknown_ssh_options(account);
         
         let ssh_config_path = self.ssh_dir.join("config");
-        let key_path = self.ssh_dir.join(&account.key_file);
-        
-        // Create a per-account alias to avoid conflicts for the same host
-        let alias = Self::alias_for(account);
-        let host_config = format!(
-            "\n# {} - {}\nHost {}\n    HostName {}\n    User git\n    IdentityFile {}\n    AddKeysToAgent yes\n    UseKeychain yes\n    IdentitiesOnly yes\n",
-            account.name,
-            account.description.as_deref().unwrap_or(&account.email),
-            alias,
-            account.host,
-            key_path.display()
-        );
         
-        let current_config = if ssh_config_path.exists() {
+        let mut updated_config = if ssh_config_path.exists() {
             fs::read_to_string(&ssh_config_path)
                 .context("Failed to read SSH config")?
         } else {
             String::new()
         };
         
-        // Check if this host is already configured
-        let host_marker = format!("# {} - ", account.name);
-        if current_config.contains(&host_marker) {
-            println!("ℹ️  SSH config for '{}' already exists, skipping...", account.name);
+        // Create a per-account alias for each host to avoid conflicts for the same host
+        let mut written = 0;
+        for (host, alias) in self.aliases_of(account) {
+            // Check if this alias is already configured
+            let host_marker = format!("# {} - ", account.name);
+            let alias_line = format!("\nHost {}\n", alias);
+            if updated_config.contains(&host_marker) && updated_config.contains(&alias_line) {
+                println!("ℹ️  SSH config for '{}' ({}) already exists, skipping...", account.name, alias);
+                continue;
+            }
+
+            updated_config.push_str(&format!(
+                "\n# {} - {}\nHost {}\n{}",
+                account.name,
+                account.description.as_deref().unwrap_or(&account.email),
+                alias,
+                self.host_block_body(account, &host)
+            ));
+            written += 1;
+        }
+        if written == 0 {
             return Ok(());
         }
         
-        let updated_config = current_config + &host_config;
-        
         fs::write(&ssh_config_path, updated_config)
             .context("Failed to write SSH config")?;
         
@@ -321,20 +1095,127 @@ impl SshManager {
         Ok(())
     }
 
-    // Compute a unique alias per account, e.g., "github-work"
-    fn alias_for(account: &SshAccount) -> String {
-        let host_prefix = account
-            .host
-            .split('.')
-            .next()
-            .unwrap_or(&account.host)
-            .to_string();
-        let name_part = account.name.replace(' ', "-");
-        format!("{}-{}", host_prefix, name_part)
+    // Key file name gam generates for an account, e.g. "id_work_github_com"
+    fn default_key_file(name: &str, host: &str) -> String {
+        format!("id_{}_{}", name.replace(" ", "_"), host.replace(".", "_"))
+    }
+
+    // Render the indented options of a Host block for one of the account's hosts
+    fn host_block_body(&self, account: &SshAccount, host: &str) -> String {
+        let endpoint = account.endpoint(host);
+        let mut body = format!("    HostName {}\n", endpoint.hostname);
+        if let Some(port) = endpoint.port {
+            body.push_str(&format!("    Port {}\n", port));
+        }
+        body.push_str(&format!("    User {}\n", endpoint.user));
+        for identity_file in account.identity_files() {
+            body.push_str(&format!("    IdentityFile {}\n", self.ssh_dir.join(identity_file).display()));
+        }
+        if let Some(proxy_jump) = endpoint.proxy_jump {
+            body.push_str(&format!("    ProxyJump {}\n", proxy_jump));
+        }
+        for (key, value) in &account.extra_options {
+            if Self::check_ssh_option(key).is_ok() {
+                body.push_str(&format!("    {} {}\n", key, value));
+            }
+        }
+        body.push_str("    AddKeysToAgent yes\n");
+        body.push_str(Self::keychain_directives());
+        body.push_str("    IdentitiesOnly yes\n");
+        body
+    }
+
+    // UseKeychain is Apple-only; stock OpenSSH fails with "Bad configuration option"
+    fn ssh_supports_use_keychain() -> bool {
+        static SUPPORTED: OnceLock<bool> = OnceLock::new();
+        *SUPPORTED.get_or_init(|| {
+            // `ssh -G` only evaluates the configuration, it never connects; `-F /dev/null` keeps
+            // mistakes elsewhere in ~/.ssh/config from looking like a missing option
+            Command::new("ssh")
+                .args(["-F", "/dev/null", "-G", "-o", "UseKeychain=yes", "gam.invalid"])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .map(|status| status.success())
+                .unwrap_or(cfg!(target_os = "macos"))
+        })
+    }
+
+    // Keychain lines for a Host block, matching what the local ssh accepts
+    fn keychain_directives() -> &'static str {
+        if Self::ssh_supports_use_keychain() {
+            "    UseKeychain yes\n"
+        } else if cfg!(target_os = "macos") {
+            // e.g. Homebrew OpenSSH on macOS: keep the option for Apple's ssh, but let this one skip it
+            "    IgnoreUnknown UseKeychain\n    UseKeychain yes\n"
+        } else {
+            ""
+        }
+    }
+
+    // Rewrite keychain lines inside gam-managed blocks; returns the new content and the number of fixes
+    fn repair_keychain_directives(&self, content: &str) -> (String, usize) {
+        let aliases: Vec<String> = self.config.accounts.values()
+            .flat_map(|account| self.aliases_of(account).into_iter().map(|(_, alias)| alias))
+            .chain(self.config.deprecated_aliases.iter().map(|entry| entry.alias.clone()))
+            .collect();
+        let replacement = Self::keychain_directives();
+
+        let mut repaired = String::with_capacity(content.len());
+        let mut fixes = 0;
+        let mut in_active = false;
+        let mut in_alias = false;
+        let mut pending_ignore: Option<&str> = None;
+
+        for line in content.split_inclusive('\n') {
+            let text = line.trim_end();
+            let indented = line.starts_with(' ') || line.starts_with('\t');
+
+            if in_active || in_alias {
+                if indented && text.trim().eq_ignore_ascii_case("IgnoreUnknown UseKeychain") {
+                    pending_ignore = Some(line);
+                    continue;
+                }
+                if indented && text.trim().to_ascii_lowercase().starts_with("usekeychain") {
+                    let original = format!("{}{}", pending_ignore.take().unwrap_or(""), line);
+                    if original != replacement {
+                        fixes += 1;
+                    }
+                    repaired.push_str(replacement);
+                    continue;
+                }
+            }
+            if let Some(ignore) = pending_ignore.take() {
+                repaired.push_str(ignore);
+            }
+
+            if text.starts_with("# gam ACTIVE START") || text.starts_with("# ssh-manager ACTIVE START") {
+                in_active = true;
+            } else if text.starts_with("# gam ACTIVE END") || text.starts_with("# ssh-manager ACTIVE END") {
+                in_active = false;
+            } else if !indented && !text.is_empty() {
+                in_alias = text
+                    .strip_prefix("Host ")
+                    .map(|host| aliases.iter().any(|alias| alias == host.trim()))
+                    .unwrap_or(false);
+            }
+            repaired.push_str(line);
+        }
+        if let Some(ignore) = pending_ignore {
+            repaired.push_str(ignore);
+        }
+
+        (repaired, fixes)
     }
 
-    // Ensure an "active" mapping for the given host to use the provided key
-    fn upsert_active_mapping(&self, host: &str, key_path: &PathBuf) -> Result<()> {
+    fn upsert_active_mapping_for_host(&self, account: &SshAccount, host: &str) -> Result<()> {
+        self.write_active_block(account, host)?;
+        println!("✅ Active SSH mapping updated for {}", host);
+        Ok(())
+    }
+
+    fn write_active_block(&self, account: &SshAccount, host: &str) -> Result<()> {
         let ssh_config_path = self.ssh_dir.join("config");
         let mut current_config = if ssh_config_path.exists() {
             fs::read_to_string(&ssh_config_path).context("Failed to read SSH config")?
@@ -349,12 +1230,8 @@ impl SshManager {
 
         let mut block = String::new();
         block.push_str(&start_marker_new);
-        block.push_str(&format!(
-            "Host {}\n    HostName {}\n    User git\n    IdentityFile {}\n    AddKeysToAgent yes\n    UseKeychain yes\n    IdentitiesOnly yes\n",
-            host,
-            host,
-            key_path.display()
-        ));
+        block.push_str(&format!("Host {}\n", host));
+        block.push_str(&self.host_block_body(account, host));
         block.push_str(&end_marker_new);
 
         let (start_marker, end_marker) = if current_config.contains(&start_marker_new) { (start_marker_new.clone(), end_marker_new.clone()) } else { (start_marker_old.clone(), end_marker_old.clone()) };
@@ -383,7 +1260,6 @@ impl SshManager {
         }
 
         fs::write(&ssh_config_path, current_config).context("Failed to write SSH config")?;
-        println!("✅ Active SSH mapping updated for {}", host);
         Ok(())
     }
 
@@ -416,11 +1292,18 @@ impl SshManager {
         Ok(())
     }
 
-    fn remove_from_ssh_agent(&self, key_path: &PathBuf) {
+    fn remove_from_ssh_agent(&self, key_path: &Path) {
         let _ = Command::new("ssh-add").arg("-d").arg(key_path).status();
     }
 
     fn remove_ssh_config_for_account(&self, account: &SshAccount) -> Result<()> {
+        for (_, alias) in self.aliases_of(account) {
+            self.remove_alias_block(&account.name, &alias)?;
+        }
+        Ok(())
+    }
+
+    fn remove_alias_block(&self, account_name: &str, alias: &str) -> Result<()> {
         let ssh_config_path = self.ssh_dir.join("config");
         if !ssh_config_path.exists() {
             return Ok(());
@@ -429,9 +1312,8 @@ impl SshManager {
         let content = fs::read_to_string(&ssh_config_path)
             .context("Failed to read SSH config")?;
 
-        let alias = Self::alias_for(account);
-        let name_escaped = regex::escape(&account.name);
-        let alias_escaped = regex::escape(&alias);
+        let name_escaped = regex::escape(account_name);
+        let alias_escaped = regex::escape(alias);
 
         // Remove block that starts with our comment header and includes the alias Host block
         let header_pattern = format!(
@@ -456,112 +1338,285 @@ impl SshManager {
         Ok(())
     }
     
+    // Describe non-default connection settings, e.g. "gitlab@git.corp.example:2222"
+    fn endpoint_summary(account: &SshAccount) -> Option<String> {
+        if account.hostname.is_none()
+            && account.port.is_none()
+            && account.ssh_user.is_none()
+            && account.proxy_jump.is_none()
+        {
+            return None;
+        }
+
+        let mut summary = format!(
+            "{}@{}",
+            account.ssh_user.as_deref().unwrap_or("git"),
+            account.hostname.as_deref().unwrap_or(&account.host)
+        );
+        if let Some(port) = account.port {
+            summary.push_str(&format!(":{}", port));
+        }
+        if let Some(proxy_jump) = &account.proxy_jump {
+            summary.push_str(&format!(" via {}", proxy_jump));
+        }
+        Some(summary)
+    }
+
     fn list_accounts(&self) -> Result<()> {
-        if self.config.accounts.is_empty() {
+        if self.config.accounts.is_empty() && self.config.https_accounts.is_empty() && self.config.deploy_keys.is_empty() {
             println!("📭 No accounts found. Use 'gam add' to create one.");
             return Ok(());
         }
         
         println!("\n📋 SSH Accounts:\n");
         
+        // Same source as `gam status`: what ~/.ssh/config makes ssh use
+        let in_ssh_config = self.active_in_ssh_config();
         for (name, account) in &self.config.accounts {
-            let active = if Some(name) == self.config.current_account.as_ref() {
-                "🟢 (active)"
+            let active_hosts = account.active_hosts_in(&in_ssh_config);
+            let active = if active_hosts.is_empty() {
+                "⚪".to_string()
+            } else if active_hosts.len() == account.hosts().len() {
+                "🟢 (active)".to_string()
             } else {
-                "⚪"
+                format!("🟢 (active on {})", active_hosts.join(", "))
             };
             
             println!("  {} {} ({})", active, name, account.email);
-            println!("      Host: {}", account.host);
+            for (host, alias) in self.aliases_of(account) {
+                println!("      Host: {} (alias {})", host, alias);
+            }
+            for (label, file, role) in Self::account_keys(account) {
+                let fingerprint = Self::key_fingerprint(&self.ssh_dir.join(file))
+                    .unwrap_or_else(|| "missing".to_string());
+                println!("      Key: {} [{}] {} ({})", label, role.as_str(), fingerprint, file);
+            }
+            if let Some(endpoint) = Self::endpoint_summary(account) {
+                println!("      SSH: {}", endpoint);
+            }
+            for (key, value) in &account.extra_options {
+                println!("      Option: {} {}", key, value);
+            }
+            for (dir, _) in self.config.directories.iter().filter(|(_, bound)| *bound == name) {
+                println!("      Bound: {}", dir);
+            }
             if let Some(desc) = &account.description {
                 println!("      Description: {}", desc);
             }
             println!();
         }
         
+        self.list_https_accounts();
+        self.list_deploy_keys();
+        self.report_active_drift(&in_ssh_config);
         Ok(())
     }
     
-    fn switch_account(&mut self) -> Result<()> {
+    // Plain account names, SSH and HTTPS, for scripts and shell completions
+    fn list_account_names(&self) -> Result<()> {
+        let mut names: Vec<&String> = self.config.accounts.keys().chain(self.config.https_accounts.keys()).collect();
+        names.sort();
+        for name in names {
+            println!("{}", name);
+        }
+        Ok(())
+    }
+    
+    fn switch_account(&mut self, account: Option<&str>) -> Result<()> {
         if self.config.accounts.is_empty() {
             println!("📭 No accounts found. Use 'gam add' to create one.");
             return Ok(());
         }
         
-        let account_names: Vec<String> = self.config.accounts.keys().cloned().collect();
+        let selected = match account {
+            Some(name) => name.to_string(),
+            None => {
+                let account_names: Vec<String> = self.config.accounts.keys().cloned().collect();
+                Select::new("Select account to activate:", account_names)
+                    .prompt()
+                    .context("Failed to get account selection")?
+            }
+        };
         
-        let selected = Select::new("Select account to activate:", account_names)
-            .prompt()
-            .context("Failed to get account selection")?;
+        self.activate_account(&selected)?;
         
-        self.config.current_account = Some(selected.clone());
+        println!("✅ Switched to account '{}'", selected);
+        Ok(())
+    }
+
+    // Account whose key the gam active block for `host` points at
+    fn active_account_for_host(&self, host: &str) -> Option<&SshAccount> {
+        let current = self.config.active_hosts.get(&host.to_ascii_lowercase())?;
+        self.config.accounts.get(current)
+            .filter(|account| account.serves_host(host))
+    }
+
+    // Hosts whose active block points at the account
+    fn active_hosts_of(&self, account: &SshAccount) -> Vec<String> {
+        account.hosts()
+            .into_iter()
+            .filter(|host| self.active_account_for_host(host).is_some_and(|active| active.name == account.name))
+            .map(|host| host.to_string())
+            .collect()
+    }
+
+    // Whether the account is active on every host it serves
+    fn is_fully_active(&self, account: &SshAccount) -> bool {
+        self.active_hosts_of(account).len() == account.hosts().len()
+    }
+
+    // Distinct accounts active on at least one host, sorted
+    fn active_account_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.config.active_hosts.values()
+            .filter(|name| self.config.accounts.contains_key(*name))
+            .map(|name| name.as_str())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn set_active_host(&mut self, host: &str, account: &str) {
+        self.config.active_hosts.insert(host.to_ascii_lowercase(), account.to_string());
+    }
+
+    fn unset_active_host(&mut self, host: &str) {
+        self.config.active_hosts.remove(&host.to_ascii_lowercase());
+    }
+
+    fn activate_account(&mut self, name: &str) -> Result<()> {
+        self.activate_account_quietly(name)?;
+        let account = &self.config.accounts[name];
+        Self::warn_unknown_ssh_options(account);
+        for host in account.hosts() {
+            println!("✅ Active SSH mapping updated for {}", host);
+        }
+        Ok(())
+    }
+
+    // Point every host of the account at its key without printing; `gam hook cd` runs on every prompt
+    fn activate_account_quietly(&mut self, name: &str) -> Result<()> {
+        let Some(account) = self.config.accounts.get(name).cloned() else {
+            return Err(anyhow::anyhow!("Account '{}' not found", name));
+        };
+
+        for host in account.hosts() {
+            self.set_active_host(host, name);
+        }
         self.save_config().context("Failed to save configuration")?;
 
         // Update active host mapping to point host -> selected account key
-        if let Some(account) = self.config.accounts.get(&selected) {
-            let key_path = self.ssh_dir.join(&account.key_file);
-            self.upsert_active_mapping(&account.host, &key_path)?;
+        for host in account.hosts() {
+            self.write_active_block(&account, host)?;
         }
-        
-        println!("✅ Switched to account '{}'", selected);
         Ok(())
     }
     
-    fn show_status(&self) -> Result<()> {
-        if let Some(current) = &self.config.current_account {
-            if let Some(account) = self.config.accounts.get(current) {
-                println!("\n🟢 Current active account: {} ({})", current, account.email);
-                println!("   Host: {}", account.host);
-                if let Some(desc) = &account.description {
-                    println!("   Description: {}", desc);
-                }
-                
-                // Test SSH connection
-                println!("\n🔄 Testing SSH connection...");
-                let key_path = self.ssh_dir.join(&account.key_file);
-                let output = Command::new("ssh")
-                    .arg("-T")
-                    .arg("-i")
-                    .arg(&key_path)
-                    .arg(&format!("git@{}", account.host))
-                    .output();
-                
-                match output {
-                    Ok(result) => {
-                        let stderr = String::from_utf8_lossy(&result.stderr);
-                        if stderr.contains("successfully authenticated") {
-                            println!("✅ SSH connection successful!");
-                        } else if stderr.contains("Permission denied") {
-                            println!("❌ SSH connection failed - key not added to {} or incorrect key", account.host);
-                        } else {
-                            println!("ℹ️  SSH test result: {}", stderr.trim());
-                        }
-                    }
-                    Err(e) => {
-                        println!("⚠️  Could not test SSH connection: {}", e);
-                    }
+    fn test_ssh_connection(&self, account: &SshAccount, host: &str) {
+        println!("\n🔄 Testing SSH connection to {}...", host);
+        let endpoint = account.endpoint(host);
+        let key_path = self.ssh_dir.join(&account.key_file);
+        let mut ssh_cmd = Command::new("ssh");
+        ssh_cmd.arg("-T").arg("-i").arg(&key_path);
+        if let Some(port) = endpoint.port {
+            ssh_cmd.arg("-p").arg(port.to_string());
+        }
+        if let Some(proxy_jump) = endpoint.proxy_jump {
+            ssh_cmd.arg("-J").arg(proxy_jump);
+        }
+        if let Some(alias) = Self::host_key_alias(account) {
+            ssh_cmd.arg("-o").arg(format!("HostKeyAlias={}", alias));
+        }
+        let output = ssh_cmd
+            .arg(format!("{}@{}", endpoint.user, endpoint.hostname))
+            .output();
+        
+        match output {
+            Ok(result) => {
+                let stderr = String::from_utf8_lossy(&result.stderr);
+                if stderr.contains("REMOTE HOST IDENTIFICATION HAS CHANGED") {
+                    println!(
+                        "❌ The host key for {} has CHANGED! Verify it, then run 'gam hosts trust {}'",
+                        Self::known_hosts_name(account, host),
+                        account.name
+                    );
+                } else if stderr.contains("Host key verification failed") {
+                    println!(
+                        "❌ Host key for {} is not trusted yet. Run 'gam hosts trust {}'",
+                        Self::known_hosts_name(account, host),
+                        account.name
+                    );
+                } else if stderr.contains("successfully authenticated") {
+                    println!("✅ SSH connection successful!");
+                } else if stderr.contains("Permission denied") {
+                    println!("❌ SSH connection failed - key not added to {} or incorrect key", host);
+                } else {
+                    println!("ℹ️  SSH test result: {}", stderr.trim());
                 }
-            } else {
-                println!("❌ Current account '{}' not found in configuration", current);
             }
-        } else {
+            Err(e) => {
+                println!("⚠️  Could not test SSH connection: {}", e);
+            }
+        }
+    }
+
+    fn show_status(&self) -> Result<()> {
+        // Report what ssh will actually use, then where gam_config.json disagrees
+        let in_ssh_config = self.active_in_ssh_config();
+        let mut active_names: Vec<&str> = in_ssh_config.values()
+            .filter_map(|state| match state {
+                ActiveBlockState::Account(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        active_names.sort();
+        active_names.dedup();
+        if active_names.is_empty() {
             println!("📭 No active account set. Use 'gam switch' to select one.");
         }
-        
-        Ok(())
+        for name in active_names {
+            let account = &self.config.accounts[name];
+            let hosts = account.active_hosts_in(&in_ssh_config);
+            if hosts.is_empty() {
+                continue;
+            }
+            println!("\n🟢 Active on {}: {} ({})", hosts.join(", "), name, account.email);
+            if let Some(endpoint) = Self::endpoint_summary(account) {
+                println!("   SSH: {}", endpoint);
+            }
+            if let Some(desc) = &account.description {
+                println!("   Description: {}", desc);
+            }
+
+            for host in &hosts {
+                self.test_ssh_connection(account, host);
+            }
+        }
+        self.report_active_drift(&in_ssh_config);
+
+        self.report_project_pin()
     }
     
-    fn remove_account(&mut self) -> Result<()> {
-        if self.config.accounts.is_empty() {
+    fn remove_account(&mut self, account: Option<&str>) -> Result<()> {
+        if self.config.accounts.is_empty() && self.config.https_accounts.is_empty() {
             println!("📭 No accounts found.");
             return Ok(());
         }
         
-        let account_names: Vec<String> = self.config.accounts.keys().cloned().collect();
-        
-        let selected = Select::new("Select account to remove:", account_names)
-            .prompt()
-            .context("Failed to get account selection")?;
+        let selected = match account {
+            Some(name) if self.config.accounts.contains_key(name) => name.to_string(),
+            Some(name) if self.config.https_accounts.contains_key(name) => name.to_string(),
+            Some(name) => return Err(anyhow::anyhow!("Account '{}' not found", name)),
+            None => {
+                let account_names: Vec<String> = self.config.accounts.keys()
+                    .chain(self.config.https_accounts.keys())
+                    .cloned()
+                    .collect();
+                Select::new("Select account to remove:", account_names)
+                    .prompt()
+                    .context("Failed to get account selection")?
+            }
+        };
         
         let confirm = Confirm::new(&format!("Are you sure you want to remove account '{}'?", selected))
             .with_default(false)
@@ -573,25 +1628,63 @@ impl SshManager {
             return Ok(());
         }
         
+        if self.config.https_accounts.contains_key(&selected) {
+            return self.remove_https_account(&selected);
+        }
+        
         if let Some(account) = self.config.accounts.remove(&selected) {
-            // Remove key files if they exist
-            let key_path = self.ssh_dir.join(&account.key_file);
-            let pub_key_path = format!("{}.pub", key_path.display());
-            
-            let _ = fs::remove_file(&key_path);
-            let _ = fs::remove_file(&pub_key_path);
+            for (_, file, _) in Self::account_keys(&account) {
+                // Remove from ssh-agent if loaded
+                self.remove_from_ssh_agent(&self.ssh_dir.join(file));
+            }
+            // Remove the key files gam generated; keys registered with --file stay
+            for file in Self::generated_key_files(&account) {
+                let key_path = self.ssh_dir.join(file);
+                let _ = fs::remove_file(&key_path);
+                let _ = fs::remove_file(format!("{}.pub", key_path.display()));
+            }
 
-            // Remove from ssh-agent if loaded
-            self.remove_from_ssh_agent(&key_path);
+            keyring::keyring_clear(&selected);
+            if let Err(e) = Vault::discard(&self.ssh_dir, &passphrase_key(&selected)) {
+                println!("⚠️  Passphrase for '{}' left in the vault: {}", selected, e);
+            }
 
             // Remove this account's alias block from ~/.ssh/config
             let _ = self.remove_ssh_config_for_account(&account);
             
-            // Remove from current account if it was active
-            if Some(&selected) == self.config.current_account.as_ref() {
-                self.config.current_account = None;
-                // Clear active mapping for this host
-                let _ = self.clear_active_mapping_for_host(&account.host);
+            // Drop directory bindings and routes pointing at the removed account
+            self.config.directories.retain(|_, bound| *bound != selected);
+            for route in self.config.routes.iter().filter(|route| route.account == selected) {
+                let _ = self.unset_route_rewrite(route);
+            }
+            self.config.routes.retain(|route| route.account != selected);
+            let retired: Vec<DeprecatedAlias> = self.config.deprecated_aliases.iter()
+                .filter(|entry| entry.account == selected)
+                .cloned()
+                .collect();
+            self.config.deprecated_aliases.retain(|entry| entry.account != selected);
+            for entry in &retired {
+                let _ = self.remove_deprecated_alias(&entry.alias);
+            }
+            
+            // Clear the active mappings that point at this account, in the config or in ~/.ssh/config
+            let mut active_hosts: Vec<String> = self.config.active_hosts.iter()
+                .filter(|(_, name)| **name == selected)
+                .map(|(host, _)| host.clone())
+                .collect();
+            for block in self.active_blocks() {
+                let offers_key = block.identity_files.iter()
+                    .any(|identity_file| self.owns_identity_file(&account, identity_file));
+                if offers_key && !active_hosts.iter().any(|host| host.eq_ignore_ascii_case(&block.host)) {
+                    active_hosts.push(block.host);
+                }
+            }
+            for host in &active_hosts {
+                let _ = self.clear_active_mapping_for_host(host);
+                self.unset_active_host(host);
+            }
+            for profile in self.config.profiles.values_mut() {
+                profile.accounts.retain(|name| *name != selected);
             }
             
             self.save_config().context("Failed to save configuration")?;
@@ -621,14 +1714,54 @@ impl SshManager {
         Ok(())
     }
     
+    fn doctor(&mut self) -> Result<()> {
+        println!("\n🩺 Checking gam setup...\n");
+
+        let ssh_config_path = self.ssh_dir.join("config");
+        if !ssh_config_path.exists() {
+            println!("📭 No SSH config file found.");
+            return Ok(());
+        }
+        let content = fs::read_to_string(&ssh_config_path)
+            .context("Failed to read SSH config")?;
+
+        if Self::ssh_supports_use_keychain() {
+            println!("ℹ️  This ssh supports UseKeychain");
+        } else {
+            println!("ℹ️  This ssh does not support UseKeychain");
+        }
+
+        let (repaired, fixes) = self.repair_keychain_directives(&content);
+        if fixes == 0 {
+            println!("✅ gam blocks in ~/.ssh/config match this platform");
+        } else {
+            println!("⚠️  {} gam block(s) contain UseKeychain lines this ssh does not accept", fixes);
+            let repair = Confirm::new("Do you want to repair them now?")
+                .with_default(true)
+                .prompt()
+                .context("Failed to get repair confirmation")?;
+
+            if repair {
+                fs::write(&ssh_config_path, repaired)
+                    .context("Failed to write SSH config")?;
+                println!("✅ SSH config repaired!");
+            }
+        }
+
+        self.prune_deprecated_aliases()
+    }
+    
     fn interactive_menu(&mut self) -> Result<()> {
         loop {
             let options = vec![
                 "📝 Add new account",
+                "🌐 Add HTTPS account",
                 "📋 List accounts",
                 "🔄 Switch account",
+                "✏️  Edit account",
                 "📊 Show status",
                 "📄 View SSH config",
+                "🩺 Run doctor",
                 "🗑️  Remove account",
                 "🚪 Exit",
             ];
@@ -639,11 +1772,14 @@ impl SshManager {
             
             match selection {
                 "📝 Add new account" => self.add_account()?,
+                "🌐 Add HTTPS account" => self.add_https_account()?,
                 "📋 List accounts" => self.list_accounts()?,
-                "🔄 Switch account" => self.switch_account()?,
+                "🔄 Switch account" => self.switch_account(None)?,
+                "✏️  Edit account" => self.edit_account(None, edit::AccountEdits::default())?,
                 "📊 Show status" => self.show_status()?,
                 "📄 View SSH config" => self.view_ssh_config()?,
-                "🗑️  Remove account" => self.remove_account()?,
+                "🩺 Run doctor" => self.doctor()?,
+                "🗑️  Remove account" => self.remove_account(None)?,
                 "🚪 Exit" => {
                     println!("👋 Goodbye!");
                     break;
@@ -662,16 +1798,99 @@ impl SshManager {
 }
 
 fn main() -> Result<()> {
+    // ssh-add runs SSH_ASKPASS with the prompt as its only argument, so skip argument parsing
+    if vault::invoked_as_askpass() {
+        let manager = SshManager::new().context("Failed to initialize SSH manager")?;
+        return manager.askpass(std::env::args().nth(1).as_deref());
+    }
+
     let args = Args::parse();
     let mut manager = SshManager::new()
         .context("Failed to initialize SSH manager")?;
     
     match args.command {
-        Some(Commands::Add) => manager.add_account(),
-        Some(Commands::List) => manager.list_accounts(),
-        Some(Commands::Switch) => manager.switch_account(),
-        Some(Commands::Remove) => manager.remove_account(),
+        Some(Commands::Add { https: false }) => manager.add_account(),
+        Some(Commands::Add { https: true }) => manager.add_https_account(),
+        Some(Commands::List { names: false }) => manager.list_accounts(),
+        Some(Commands::List { names: true }) => manager.list_account_names(),
+        Some(Commands::Switch { account }) => manager.switch_account(account.as_deref()),
+        Some(Commands::Remove { account }) => manager.remove_account(account.as_deref()),
+        Some(Commands::Edit { account, edits }) => manager.edit_account(account.as_deref(), edits),
+        Some(Commands::Alias { command }) => match command {
+            AliasCommands::Set { account, alias } => manager.alias_set(&account, alias.as_deref()),
+            AliasCommands::Template { template } => manager.alias_template_set(template.as_deref()),
+        },
+        Some(Commands::Rename { old, new, grace_days, workspaces }) => {
+            manager.rename_account(&old, &new, grace_days, &workspaces)
+        }
         Some(Commands::Status) => manager.show_status(),
+        Some(Commands::Doctor) => manager.doctor(),
+        Some(Commands::Sync { from }) => manager.sync_active(from),
+        Some(Commands::Hosts { command }) => match command {
+            HostsCommands::Trust { account } => manager.trust_host(&account),
+            HostsCommands::Add { account, host } => manager.hosts_add(&account, &host),
+            HostsCommands::Remove { account, host } => manager.hosts_remove(&account, &host),
+        },
+        Some(Commands::Agent { command }) => match command {
+            AgentCommands::Status => manager.agent_status(),
+            AgentCommands::Load { account, lifetime } => manager.agent_load(&account, lifetime.as_deref()),
+            AgentCommands::Unload { account } => manager.agent_unload(&account),
+            AgentCommands::Only { account, lifetime } => manager.agent_only(account.as_deref(), lifetime.as_deref()),
+        },
+        Some(Commands::Bind { account, dir }) => manager.bind_directory(&account, dir),
+        Some(Commands::Unbind { dir }) => manager.unbind_directory(dir),
+        Some(Commands::Init { shell }) => {
+            print!("{}", shell::init_script(shell));
+            Ok(())
+        }
+        Some(Commands::Prompt { format }) => manager.print_prompt(&format),
+        Some(Commands::Hook { command }) => match command {
+            HookCommands::Cd => manager.hook_cd(),
+            HookCommands::Install { global } => manager.install_hooks(global),
+            HookCommands::Uninstall { global } => manager.uninstall_hooks(global),
+        },
+        Some(Commands::Verify { remote, url, push }) => manager.verify(remote.as_deref(), url.as_deref(), push),
+        Some(Commands::Use { account, auto }) => manager.use_account(account.as_deref(), auto),
+        Some(Commands::Route { command }) => match command {
+            RouteCommands::Add { pattern, account } => manager.route_add(&pattern, &account),
+            RouteCommands::List => manager.route_list(),
+            RouteCommands::Apply => manager.route_apply(),
+            RouteCommands::Remove { pattern } => manager.route_remove(&pattern),
+        },
+        Some(Commands::Ssh { args }) => manager.ssh_wrapper(&args),
+        Some(Commands::Credential { action }) => manager.credential(action),
+        Some(Commands::Passphrase { account, remove }) => manager.change_passphrase(&account, remove),
+        Some(Commands::DeployKey { command }) => match command {
+            DeployKeyCommands::Add { repository, host, write, forge, token_account } => {
+                manager.deploy_key_add(&repository, &host, write, forge, token_account.as_deref())
+            }
+            DeployKeyCommands::List => manager.deploy_key_list(),
+            DeployKeyCommands::Remove { repository, host, forge, token_account } => {
+                manager.deploy_key_remove(&repository, &host, forge, token_account.as_deref())
+            }
+        },
+        Some(Commands::Profile { command }) => match command {
+            ProfileCommands::Add { name, accounts } => manager.profile_add(&name, &accounts),
+            ProfileCommands::List => manager.profile_list(),
+            ProfileCommands::Use { name } => manager.profile_use(&name),
+            ProfileCommands::Remove { name } => manager.profile_remove(&name),
+        },
+        Some(Commands::Key { command }) => match command {
+            KeyCommands::Add { account, role, label, file } => {
+                manager.key_add(&account, role, label.as_deref(), file.as_deref())
+            }
+            KeyCommands::Remove { account, key, delete } => manager.key_remove(&account, &key, delete),
+        },
+        Some(Commands::Vault { command }) => match command {
+            VaultCommands::Status => manager.vault_status(),
+            VaultCommands::Store { account, keyring } => manager.vault_store(&account, keyring),
+            VaultCommands::Forget { account } => manager.vault_forget(&account),
+        },
+        Some(Commands::Askpass { prompt }) => manager.askpass(prompt.as_deref()),
+        Some(Commands::Completions { shell }) => {
+            print!("{}", shell::completion_script(shell));
+            Ok(())
+        }
         None => manager.interactive_menu(),
     }
 }
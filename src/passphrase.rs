@@ -0,0 +1,138 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+use inquire::Password;
+
+use crate::keyring::{keyring_clear, keyring_lookup, keyring_store};
+use crate::vault::{passphrase_key, Vault};
+use crate::SshManager;
+
+impl SshManager {
+    // Rewrap an account's private keys with a new (or no) passphrase
+    pub(crate) fn change_passphrase(&self, account_name: &str, remove: bool) -> Result<()> {
+        let account = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?;
+        let key_path = self.ssh_dir.join(&account.key_file);
+        if !key_path.exists() {
+            return Err(anyhow::anyhow!("Key file {} not found", key_path.display()));
+        }
+
+        let in_keyring = keyring_lookup(account_name).is_some();
+        let in_vault = Vault::contains(&self.ssh_dir, &passphrase_key(account_name));
+
+        // Use the stored passphrase when it still works, else ask
+        let current = match self.stored_passphrase(account_name)? {
+            Some(stored) if Self::passphrase_unlocks(&key_path, &stored) => stored,
+            _ if Self::passphrase_unlocks(&key_path, "") => String::new(),
+            _ => {
+                let typed = Password::new(&format!("Current passphrase for {}:", key_path.display()))
+                    .without_confirmation()
+                    .prompt()
+                    .context("Failed to get current passphrase")?;
+                if !Self::passphrase_unlocks(&key_path, &typed) {
+                    return Err(anyhow::anyhow!("Incorrect passphrase for {}", key_path.display()));
+                }
+                typed
+            }
+        };
+
+        let new = if remove {
+            String::new()
+        } else {
+            Password::new("New passphrase (leave empty to remove it):")
+                .with_custom_confirmation_message("Confirm new passphrase:")
+                .prompt()
+                .context("Failed to get new passphrase")?
+        };
+
+        // Extra keys generated by `gam key add` share the primary key's passphrase
+        let mut rewrapped = Vec::new();
+        for (_, file, _) in Self::account_keys(account) {
+            let path = self.ssh_dir.join(file);
+            if !path.exists() {
+                continue;
+            }
+            if path != key_path && !Self::passphrase_unlocks(&path, &current) {
+                println!("⚠️  {} uses another passphrase; left unchanged", path.display());
+                continue;
+            }
+
+            let mut keygen = Command::new("ssh-keygen");
+            keygen.arg("-p").arg("-f").arg(&path).arg("-q");
+            Self::feed_passphrase(&mut keygen, &current)?;
+            Self::feed_new_passphrase(&mut keygen, &new)?;
+            let status = keygen.status().context("Failed to run ssh-keygen")?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("ssh-keygen failed to change the passphrase of {}", path.display()));
+            }
+            if new.is_empty() {
+                println!("✅ Passphrase removed from {}", path.display());
+            } else {
+                println!("✅ Passphrase changed for {}", path.display());
+            }
+            rewrapped.push(path);
+        }
+
+        // Keep stored copies in step with the key
+        if in_keyring {
+            if new.is_empty() {
+                keyring_clear(account_name);
+                println!("✅ Passphrase removed from the desktop keyring");
+            } else {
+                keyring_store(account_name, &new)?;
+                println!("✅ Desktop keyring updated");
+            }
+        }
+        if in_vault {
+            let mut vault = Vault::open(&self.ssh_dir)?;
+            if new.is_empty() {
+                vault.remove(&passphrase_key(account_name));
+            } else {
+                vault.set(&passphrase_key(account_name), &new);
+            }
+            vault.save()?;
+            println!("✅ Vault updated");
+        }
+
+        for path in &rewrapped {
+            self.reload_key(path, &new)?;
+        }
+        Ok(())
+    }
+
+    // Replace the agent's copy of a key; on macOS this also refreshes the Keychain entry
+    fn reload_key(&self, key_path: &Path, passphrase: &str) -> Result<()> {
+        let loaded = match (Self::key_fingerprint(key_path), Self::agent_fingerprints()) {
+            (Some(fingerprint), Ok(fingerprints)) => fingerprints.contains(&fingerprint),
+            _ => false,
+        };
+        if !loaded && !cfg!(target_os = "macos") {
+            return Ok(());
+        }
+
+        let _ = Command::new("ssh-add")
+            .arg("-d")
+            .arg(key_path)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+
+        let mut add_cmd = Command::new("ssh-add");
+        if cfg!(target_os = "macos") {
+            add_cmd.arg("--apple-use-keychain");
+        }
+        if !passphrase.is_empty() {
+            Self::feed_passphrase(&mut add_cmd, passphrase)?;
+        }
+        let status = add_cmd
+            .arg(key_path)
+            .status()
+            .context("Failed to run ssh-add")?;
+        if status.success() {
+            println!("✅ Key reloaded in ssh-agent");
+        } else {
+            println!("⚠️  Could not reload the key in ssh-agent; run 'gam agent load'");
+        }
+        Ok(())
+    }
+}
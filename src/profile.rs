@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+
+use crate::validate::validate_account_name;
+use crate::{Profile, SshManager};
+
+impl SshManager {
+    pub(crate) fn profile_add(&mut self, name: &str, accounts: &[String]) -> Result<()> {
+        let name = name.trim();
+        validate_account_name(name).map_err(|e| anyhow::anyhow!("Invalid profile name: {}", e))?;
+        if self.config.profiles.contains_key(name) {
+            return Err(anyhow::anyhow!("Profile '{}' already exists; remove it first", name));
+        }
+
+        // Each host has one active block, so a profile can pick only one account per host
+        let mut hosts: Vec<(String, &str)> = Vec::new();
+        for account_name in accounts {
+            let account = self.config.accounts.get(account_name)
+                .with_context(|| format!("Account '{}' not found", account_name))?;
+            for host in account.hosts() {
+                if let Some((_, other)) = hosts.iter().find(|(taken, _)| taken.eq_ignore_ascii_case(host)) {
+                    return Err(anyhow::anyhow!(
+                        "'{}' and '{}' both serve {}; a profile can activate one account per host",
+                        other,
+                        account_name,
+                        host
+                    ));
+                }
+                hosts.push((host.to_string(), account_name));
+            }
+        }
+
+        self.config.profiles.insert(name.to_string(), Profile { accounts: accounts.to_vec() });
+        self.save_config().context("Failed to save configuration")?;
+        println!("✅ Profile '{}' created with {}", name, accounts.join(", "));
+        Ok(())
+    }
+
+    // Whether every account of the profile is active on all of its hosts
+    fn profile_in_effect(&self, profile: &Profile) -> bool {
+        profile.accounts.iter().all(|name| {
+            self.config.accounts.get(name).is_some_and(|account| self.is_fully_active(account))
+        })
+    }
+
+    pub(crate) fn profile_list(&self) -> Result<()> {
+        if self.config.profiles.is_empty() {
+            println!("📭 No profiles found. Use 'gam profile add <name> <accounts...>' to create one.");
+            return Ok(());
+        }
+
+        println!("\n🗂️  Profiles:\n");
+        for (name, profile) in &self.config.profiles {
+            let marker = if self.profile_in_effect(profile) { "🟢" } else { "⚪" };
+            println!("  {} {}", marker, name);
+            for account_name in &profile.accounts {
+                match self.config.accounts.get(account_name) {
+                    Some(account) => println!("      {} on {}", account_name, account.hosts().join(", ")),
+                    None => println!("      {} (missing)", account_name),
+                }
+            }
+            println!();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn profile_use(&mut self, name: &str) -> Result<()> {
+        let profile = self.config.profiles.get(name)
+            .with_context(|| format!("Profile '{}' not found", name))?
+            .clone();
+        if profile.accounts.is_empty() {
+            return Err(anyhow::anyhow!("Profile '{}' has no accounts left", name));
+        }
+
+        for account_name in &profile.accounts {
+            self.activate_account(account_name)?;
+        }
+        println!("✅ Switched to profile '{}' ({})", name, profile.accounts.join(", "));
+        Ok(())
+    }
+
+    pub(crate) fn profile_remove(&mut self, name: &str) -> Result<()> {
+        if self.config.profiles.remove(name).is_none() {
+            return Err(anyhow::anyhow!("Profile '{}' not found", name));
+        }
+        self.save_config().context("Failed to save configuration")?;
+        println!("✅ Profile '{}' removed", name);
+        Ok(())
+    }
+}
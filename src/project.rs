@@ -0,0 +1,181 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::{Context, Result};
+use inquire::Select;
+use serde::Deserialize;
+
+use crate::{SshAccount, SshManager};
+
+pub(crate) const PROJECT_FILE: &str = ".gam.toml";
+
+// Requirement committed at a repository root, e.g. `email_domain = "acme.com"`
+#[derive(Debug, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProjectPin {
+    account: Option<String>,
+    email: Option<String>,
+    email_domain: Option<String>,
+    host: Option<String>,
+}
+
+impl ProjectPin {
+    // Nearest `.gam.toml` walking up from `dir`
+    pub(crate) fn discover(dir: &Path) -> Result<Option<(PathBuf, ProjectPin)>> {
+        for ancestor in dir.ancestors() {
+            let path = ancestor.join(PROJECT_FILE);
+            if path.is_file() {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let pin = toml::from_str(&content)
+                    .with_context(|| format!("Failed to parse {}", path.display()))?;
+                return Ok(Some((path, pin)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn matches(&self, account: &SshAccount) -> bool {
+        self.account.as_ref().is_none_or(|name| *name == account.name)
+            && self.email.as_ref().is_none_or(|email| email.eq_ignore_ascii_case(&account.email))
+            && self.email_domain.as_ref().is_none_or(|domain| Self::email_in_domain(&account.email, domain))
+            && self.host.as_ref().is_none_or(|host| account.serves_host(host))
+    }
+
+    pub(crate) fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    pub(crate) fn matches_email(&self, email: &str) -> bool {
+        self.email.as_ref().is_none_or(|pinned| pinned.eq_ignore_ascii_case(email))
+            && self.email_domain.as_ref().is_none_or(|domain| Self::email_in_domain(email, domain))
+    }
+
+    fn email_in_domain(email: &str, domain: &str) -> bool {
+        let domain = domain.trim_start_matches('@');
+        email
+            .rsplit_once('@')
+            .is_some_and(|(_, email_domain)| email_domain.eq_ignore_ascii_case(domain))
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(account) = &self.account {
+            parts.push(format!("account = {}", account));
+        }
+        if let Some(email) = &self.email {
+            parts.push(format!("email = {}", email));
+        }
+        if let Some(domain) = &self.email_domain {
+            parts.push(format!("email domain = {}", domain));
+        }
+        if let Some(host) = &self.host {
+            parts.push(format!("host = {}", host));
+        }
+        if parts.is_empty() {
+            "no requirement".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+// `user.email` git would record for commits in the current directory
+pub(crate) fn git_user_email() -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "user.email"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let email = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if email.is_empty() { None } else { Some(email) }
+}
+
+impl SshManager {
+    // Local accounts satisfying the pin, sorted by name
+    pub(crate) fn accounts_matching(&self, pin: &ProjectPin) -> Vec<&SshAccount> {
+        let mut matching: Vec<&SshAccount> = self.config.accounts.values()
+            .filter(|account| pin.matches(account))
+            .collect();
+        matching.sort_by(|a, b| a.name.cmp(&b.name));
+        matching
+    }
+
+    pub(crate) fn use_account(&mut self, account: Option<&str>, auto: bool) -> Result<()> {
+        let selected = match (account, auto) {
+            (Some(name), _) => name.to_string(),
+            (None, true) => self.resolve_project_account()?,
+            (None, false) => return Err(anyhow::anyhow!("Pass an account name or --auto")),
+        };
+
+        let active = self.config.accounts.get(&selected).is_some_and(|account| self.is_fully_active(account));
+        if active {
+            println!("ℹ️  Account '{}' is already active", selected);
+            return Ok(());
+        }
+
+        self.activate_account(&selected)?;
+        println!("✅ Switched to account '{}'", selected);
+        Ok(())
+    }
+
+    fn resolve_project_account(&self) -> Result<String> {
+        let cwd = env::current_dir().context("Failed to read current directory")?;
+        let (path, pin) = ProjectPin::discover(&cwd)?
+            .with_context(|| format!("No {} found in {} or its parents", PROJECT_FILE, cwd.display()))?;
+
+        let matching = self.accounts_matching(&pin);
+        match matching.as_slice() {
+            [] => Err(anyhow::anyhow!(
+                "No local account satisfies {} ({})",
+                path.display(),
+                pin.describe()
+            )),
+            [account] => Ok(account.name.clone()),
+            _ => {
+                // Prefer the account already in effect when it qualifies
+                if let Some(effective) = self.effective_account_for_repo(pin.host()) {
+                    if matching.iter().any(|account| account.name == effective) {
+                        return Ok(effective.to_string());
+                    }
+                }
+                let names: Vec<String> = matching.iter().map(|account| account.name.clone()).collect();
+                Select::new(&format!("Several accounts satisfy {}; select one:", PROJECT_FILE), names)
+                    .prompt()
+                    .context("Failed to get account selection")
+            }
+        }
+    }
+
+    // Compare the project's pinned requirement with the effective identity
+    pub(crate) fn report_project_pin(&self) -> Result<()> {
+        let cwd = env::current_dir().context("Failed to read current directory")?;
+        let Some((path, pin)) = ProjectPin::discover(&cwd)? else {
+            return Ok(());
+        };
+
+        println!("\n📌 Project pin ({}): {}", path.display(), pin.describe());
+
+        match self.effective_account_for_repo(pin.host()).and_then(|name| self.config.accounts.get(name)) {
+            Some(account) if pin.matches(account) => {
+                println!("✅ Effective account '{}' satisfies the pin", account.name);
+            }
+            Some(account) => {
+                println!("⚠️  Effective account '{}' does not satisfy the pin. Run 'gam use --auto'", account.name);
+            }
+            None => println!("⚠️  No effective account. Run 'gam use --auto'"),
+        }
+
+        if let Some(email) = git_user_email() {
+            if pin.matches_email(&email) {
+                println!("✅ git user.email {} satisfies the pin", email);
+            } else {
+                println!("⚠️  git user.email {} does not satisfy the pin", email);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{Context, Result};
+
+use crate::githooks::parse_ssh_remote;
+use crate::{DeprecatedAlias, SshAccount, SshManager};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+// Ten years; old aliases are meant to be temporary
+const MAX_GRACE_DAYS: u64 = 3650;
+// How deep `--workspace` looks for repositories below each directory
+const WORKSPACE_SCAN_DEPTH: usize = 4;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn deprecated_start_marker(alias: &str) -> String {
+    format!("# gam DEPRECATED START [{}]\n", alias)
+}
+
+fn deprecated_end_marker(alias: &str) -> String {
+    format!("# gam DEPRECATED END [{}]\n", alias)
+}
+
+// Swap the host of an SSH remote URL, keeping user, port and path
+fn replace_remote_host(url: &str, old_host: &str, new_host: &str) -> Option<String> {
+    let remote = parse_ssh_remote(url)?;
+    if remote.host != old_host {
+        return None;
+    }
+    // The host sits after the scheme and optional "user@", before ':' or '/'
+    let authority_start = url.find("://").map_or(0, |idx| idx + 3);
+    let rest = &url[authority_start..];
+    let authority_len = rest.find([':', '/']).unwrap_or(rest.len());
+    let host_start = authority_start + rest[..authority_len].rfind('@').map_or(0, |idx| idx + 1);
+    Some(format!("{}{}{}", &url[..host_start], new_host, &url[host_start + old_host.len()..]))
+}
+
+// `value` as a literal in git's value patterns (POSIX extended regexes)
+fn regex_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.^$*+?()[]{}|".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Git repositories at or below `dir`, without descending into repositories or hidden directories
+fn find_repositories(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    if dir.join(".git").exists() {
+        found.push(dir.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && path.is_dir() {
+            find_repositories(&path, depth - 1, found);
+        }
+    }
+}
+
+impl SshManager {
+    pub(crate) fn rename_account(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        grace_days: u64,
+        workspaces: &[PathBuf],
+    ) -> Result<()> {
+        let old = self.config.accounts.get(old_name)
+            .with_context(|| format!("Account '{}' not found", old_name))?
+            .clone();
+        // Checked before anything changes so a bad value cannot leave a half-done rename
+        let expires = Some(grace_days)
+            .filter(|days| *days <= MAX_GRACE_DAYS)
+            .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+            .and_then(|grace| now().checked_add(grace))
+            .with_context(|| format!("--grace-days must be at most {}", MAX_GRACE_DAYS))?;
+        let mut new = old.clone();
+        new.name = new_name.trim().to_string();
+        if !self.check_account_edit(&old, &new)? {
+            println!("ℹ️  '{}' already has that name", old_name);
+            return Ok(());
+        }
+
+        let changed = self.changed_aliases(&old, &new);
+        let ssh_config = fs::read_to_string(self.ssh_dir.join("config")).unwrap_or_default();
+        let had_alias_block = ssh_config.contains(&format!("# {} - ", old.name));
+
+        self.apply_account_edit(&old, new.clone())?;
+        println!("✅ Account '{}' renamed to '{}'", old.name, new.name);
+
+        // Existing clones keep working through the old aliases until they expire
+        if had_alias_block && grace_days > 0 {
+            for (host, old_alias, _) in &changed {
+                let deprecated = DeprecatedAlias {
+                    alias: old_alias.clone(),
+                    account: new.name.clone(),
+                    expires,
+                    host: Some(host.clone()).filter(|host| !host.eq_ignore_ascii_case(&new.host)),
+                };
+                self.write_deprecated_alias(&deprecated)?;
+                self.config.deprecated_aliases.retain(|entry| entry.alias != *old_alias);
+                self.config.deprecated_aliases.push(deprecated);
+                println!(
+                    "ℹ️  Old alias {} keeps working for {} day(s); gam removes it afterwards",
+                    old_alias, grace_days
+                );
+            }
+        }
+        self.save_config().context("Failed to save configuration")?;
+
+        for workspace in workspaces {
+            for (_, old_alias, new_alias) in &changed {
+                self.rewrite_workspace_remotes(workspace, old_alias, new_alias)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Host block for a retired alias pointing at the account's current key
+    pub(crate) fn write_deprecated_alias(&self, deprecated: &DeprecatedAlias) -> Result<()> {
+        let account = self.config.accounts.get(&deprecated.account)
+            .with_context(|| format!("Account '{}' not found", deprecated.account))?;
+        // Fall back to the primary host once the alias' own host is no longer served
+        let host = deprecated.host.as_deref()
+            .filter(|host| account.serves_host(host))
+            .unwrap_or(&account.host);
+        let ssh_config_path = self.ssh_dir.join("config");
+        let mut content = fs::read_to_string(&ssh_config_path).unwrap_or_default();
+        content = Self::without_deprecated_block(&content, &deprecated.alias);
+
+        if !content.ends_with('\n') && !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(&deprecated_start_marker(&deprecated.alias));
+        content.push_str(&format!("Host {}\n", deprecated.alias));
+        content.push_str(&self.host_block_body(account, host));
+        content.push_str(&deprecated_end_marker(&deprecated.alias));
+
+        fs::write(&ssh_config_path, content).context("Failed to write SSH config")?;
+        Ok(())
+    }
+
+    fn without_deprecated_block(content: &str, alias: &str) -> String {
+        let start_marker = deprecated_start_marker(alias);
+        let end_marker = deprecated_end_marker(alias);
+        let Some(start_idx) = content.find(&start_marker) else {
+            return content.to_string();
+        };
+        let end_idx = content[start_idx..]
+            .find(&end_marker)
+            .map_or(content.len(), |rel| start_idx + rel + end_marker.len());
+        format!("{}{}", &content[..start_idx], &content[end_idx..])
+    }
+
+    pub(crate) fn remove_deprecated_alias(&self, alias: &str) -> Result<()> {
+        let ssh_config_path = self.ssh_dir.join("config");
+        let content = fs::read_to_string(&ssh_config_path).unwrap_or_default();
+        let updated = Self::without_deprecated_block(&content, alias);
+        if updated != content {
+            fs::write(&ssh_config_path, updated).context("Failed to write SSH config")?;
+        }
+        Ok(())
+    }
+
+    // Account a retired alias still routes to
+    pub(crate) fn deprecated_alias_account(&self, alias: &str) -> Option<&SshAccount> {
+        let deprecated = self.config.deprecated_aliases.iter().find(|entry| entry.alias == alias)?;
+        self.config.accounts.get(&deprecated.account)
+    }
+
+    // Drop retired aliases whose grace period is over, or whose account is gone; the caller saves
+    fn drop_expired_deprecated_aliases(&mut self) -> Result<Vec<DeprecatedAlias>> {
+        let current = now();
+        let (expired, kept): (Vec<DeprecatedAlias>, Vec<DeprecatedAlias>) = self.config.deprecated_aliases
+            .drain(..)
+            .partition(|entry| entry.expires <= current || !self.config.accounts.contains_key(&entry.account));
+        self.config.deprecated_aliases = kept;
+        for entry in &expired {
+            self.remove_deprecated_alias(&entry.alias)?;
+        }
+        Ok(expired)
+    }
+
+    // Run on every load so grace periods end without 'gam doctor'; stderr keeps `gam prompt` clean
+    pub(crate) fn expire_deprecated_aliases(&mut self) -> Result<bool> {
+        let expired = self.drop_expired_deprecated_aliases()?;
+        for entry in &expired {
+            eprintln!("ℹ️  gam: deprecated alias {} expired and was removed", entry.alias);
+        }
+        Ok(!expired.is_empty())
+    }
+
+    pub(crate) fn prune_deprecated_aliases(&mut self) -> Result<()> {
+        let expired = self.drop_expired_deprecated_aliases()?;
+        let current = now();
+        for entry in &self.config.deprecated_aliases {
+            let days_left = (entry.expires - current).div_ceil(SECONDS_PER_DAY);
+            println!("ℹ️  Deprecated alias {} -> '{}' expires in {} day(s)", entry.alias, entry.account, days_left);
+        }
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        for entry in &expired {
+            println!("✅ Removed deprecated alias {}", entry.alias);
+        }
+        self.save_config().context("Failed to save configuration")
+    }
+
+    // Point remotes of every repository under `workspace` from the old alias to the new one
+    fn rewrite_workspace_remotes(&self, workspace: &Path, old_alias: &str, new_alias: &str) -> Result<()> {
+        let mut repositories = Vec::new();
+        find_repositories(workspace, WORKSPACE_SCAN_DEPTH, &mut repositories);
+        if repositories.is_empty() {
+            println!("ℹ️  No git repositories found in {}", workspace.display());
+            return Ok(());
+        }
+
+        let mut rewritten = 0;
+        for repository in &repositories {
+            let output = Command::new("git")
+                .arg("-C")
+                .arg(repository)
+                .args(["config", "--get-regexp", r"^remote\..*\.(url|pushurl)$"])
+                .output()
+                .context("Failed to run git config")?;
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                let Some((key, url)) = line.split_once(' ') else {
+                    continue;
+                };
+                let Some(new_url) = replace_remote_host(url, old_alias, new_alias) else {
+                    continue;
+                };
+                // Only this value: a remote may carry several pushurl entries
+                let status = Command::new("git")
+                    .arg("-C")
+                    .arg(repository)
+                    .args(["config", "--replace-all", key, &new_url, &format!("^{}$", regex_escape(url))])
+                    .status()
+                    .context("Failed to run git config")?;
+                if status.success() {
+                    rewritten += 1;
+                    println!("✅ {}: {} -> {}", repository.display(), url, new_url);
+                } else {
+                    println!("⚠️  {}: could not update {}", repository.display(), key);
+                }
+            }
+        }
+        println!(
+            "ℹ️  {} remote URL(s) rewritten across {} repository(ies) under {}",
+            rewritten,
+            repositories.len(),
+            workspace.display()
+        );
+        Ok(())
+    }
+}
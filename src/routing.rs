@@ -0,0 +1,267 @@
+use std::process::Command;
+use anyhow::{Context, Result};
+
+use crate::{Route, SshAccount, SshManager};
+
+// ssh options that consume the following argument
+const SSH_OPTIONS_WITH_VALUE: &str = "BbcDEeFIiJLlmOopQRSWw";
+
+impl Route {
+    // "github.com/acme-corp/*" -> ("github.com", "acme-corp/*")
+    fn parts(&self) -> Option<(&str, &str)> {
+        self.pattern.split_once('/')
+    }
+
+    fn is_exact(&self) -> bool {
+        !self.pattern.ends_with("/*")
+    }
+
+    pub(crate) fn matches(&self, host: &str, path: &str) -> bool {
+        let Some((route_host, route_path)) = self.parts() else {
+            return false;
+        };
+        if !route_host.eq_ignore_ascii_case(host) {
+            return false;
+        }
+
+        let repo = normalize_repo_path(path);
+        match route_path.strip_suffix("/*") {
+            Some(owner) => repo
+                .to_ascii_lowercase()
+                .starts_with(&format!("{}/", owner.to_ascii_lowercase())),
+            None => repo.eq_ignore_ascii_case(route_path.trim_end_matches(".git")),
+        }
+    }
+}
+
+// "'/acme-corp/x.git'" or "~/acme-corp/x.git" -> "acme-corp/x"
+pub(crate) fn normalize_repo_path(path: &str) -> String {
+    path.trim_matches(|c| c == '\'' || c == '"')
+        .trim_start_matches("~/")
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_string()
+}
+
+// Patterns look like "host/owner/*" or "host/owner/repo"
+fn check_route_pattern(pattern: &str) -> Result<()> {
+    let segments: Vec<&str> = pattern.split('/').collect();
+    let valid = segments.len() == 3
+        && segments.iter().all(|segment| !segment.is_empty())
+        && !segments[..2].contains(&"*");
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Invalid route '{}'; use 'host/owner/*' or 'host/owner/repo'",
+            pattern
+        ))
+    }
+}
+
+// Split git's ssh invocation into the destination host and the repository path it requests
+fn parse_ssh_invocation(args: &[String]) -> Option<(String, Option<String>)> {
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            i += 1;
+            break;
+        }
+        if let Some(flags) = arg.strip_prefix('-') {
+            // "-p 22" consumes the next argument, "-p22" does not
+            if flags.len() == 1 && SSH_OPTIONS_WITH_VALUE.contains(flags) {
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        break;
+    }
+
+    let destination = args.get(i)?;
+    let host = destination.rsplit_once('@').map_or(destination.as_str(), |(_, host)| host);
+
+    // Remote command, e.g. "git-upload-pack 'acme-corp/x.git'"
+    let command = args[i + 1..].join(" ");
+    let path = command
+        .split_once(char::is_whitespace)
+        .map(|(_, path)| normalize_repo_path(path.trim()))
+        .filter(|path| !path.is_empty());
+    Some((host.to_string(), path))
+}
+
+impl SshManager {
+    // Most specific route for a repository: exact repositories win over owner wildcards
+    pub(crate) fn route_for(&self, host: &str, path: &str) -> Option<&Route> {
+        self.config.routes
+            .iter()
+            .filter(|route| route.matches(host, path))
+            .max_by_key(|route| (route.is_exact(), route.pattern.len()))
+    }
+
+    // Account whose key ssh will offer: an alias, else a route, else the active mapping
+    pub(crate) fn account_for_ssh_target(&self, host: &str, path: Option<&str>) -> Option<&SshAccount> {
+        if let Some(account) = self.account_for_alias(host) {
+            return Some(account);
+        }
+        if let Some(account) = self.deprecated_alias_account(host) {
+            return Some(account);
+        }
+        if let Some(route) = path.and_then(|path| self.route_for(host, path)) {
+            if let Some(account) = self.config.accounts.get(&route.account) {
+                return Some(account);
+            }
+        }
+        self.active_account_for_host(host)
+    }
+
+    pub(crate) fn route_add(&mut self, pattern: &str, account: &str) -> Result<()> {
+        check_route_pattern(pattern)?;
+        if !self.config.accounts.contains_key(account) && !self.config.https_accounts.contains_key(account) {
+            return Err(anyhow::anyhow!("Account '{}' not found", account));
+        }
+
+        if let Some(previous) = self.config.routes.iter().find(|route| route.pattern == pattern) {
+            self.unset_route_rewrite(previous)?;
+        }
+        self.config.routes.retain(|route| route.pattern != pattern);
+        let route = Route {
+            pattern: pattern.to_string(),
+            account: account.to_string(),
+        };
+        self.apply_route_rewrite(&route)?;
+        self.config.routes.push(route);
+        self.save_config().context("Failed to save configuration")?;
+        println!("✅ Route {} -> '{}' added", pattern, account);
+        Ok(())
+    }
+
+    pub(crate) fn route_remove(&mut self, pattern: &str) -> Result<()> {
+        let Some(route) = self.config.routes.iter().find(|route| route.pattern == pattern).cloned() else {
+            println!("ℹ️  No route '{}' found", pattern);
+            return Ok(());
+        };
+        self.unset_route_rewrite(&route)?;
+        self.config.routes.retain(|route| route.pattern != pattern);
+        self.save_config().context("Failed to save configuration")?;
+        println!("✅ Route {} removed", pattern);
+        Ok(())
+    }
+
+    // Rewrite gitconfig insteadOf entries for every route, e.g. after an alias changed
+    pub(crate) fn route_apply(&self) -> Result<()> {
+        for route in &self.config.routes {
+            self.unset_route_rewrite(route)?;
+            self.apply_route_rewrite(route)?;
+        }
+        println!("✅ gitconfig rewrites refreshed for {} route(s)", self.config.routes.len());
+        Ok(())
+    }
+
+    // `url.<alias>:<owner>/.insteadOf` base and the plain URLs it replaces
+    fn route_rewrite(&self, route: &Route) -> Option<(String, Vec<String>)> {
+        let account = self.config.accounts.get(&route.account)?;
+        let (host, path) = route.parts()?;
+        if !account.serves_host(host) {
+            return None;
+        }
+
+        // Owner wildcards rewrite the "owner/" prefix; exact repositories only their ".git" URL,
+        // since insteadOf is a prefix match and "owner/repo" would also catch "owner/repo-other"
+        let prefix = match path.strip_suffix("/*") {
+            Some(owner) => format!("{}/", owner),
+            None => format!("{}.git", path.trim_end_matches(".git")),
+        };
+        let user = account.endpoint(host).user;
+        // No user in the rewritten URL so the alias' `User` applies
+        let base = format!("{}:{}", self.alias_for_host(account, host), prefix);
+        let sources = vec![
+            format!("{}@{}:{}", user, host, prefix),
+            format!("ssh://{}@{}/{}", user, host, prefix),
+        ];
+        Some((base, sources))
+    }
+
+    pub(crate) fn apply_route_rewrite(&self, route: &Route) -> Result<()> {
+        let Some((base, sources)) = self.route_rewrite(route) else {
+            if let Some(account) = self.config.accounts.get(&route.account) {
+                println!(
+                    "⚠️  Route {} is not on {} (account '{}'); no gitconfig rewrite written",
+                    route.pattern, account.hosts().join(", "), account.name
+                );
+            }
+            return Ok(());
+        };
+
+        for source in &sources {
+            let status = Command::new("git")
+                .args(["config", "--global", "--add"])
+                .arg(format!("url.{}.insteadOf", base))
+                .arg(source)
+                .status()
+                .context("Failed to run git config")?;
+            if !status.success() {
+                return Err(anyhow::anyhow!("Failed to write url.{}.insteadOf", base));
+            }
+        }
+        println!("✅ git will use {} for {}", base, sources[0]);
+        Ok(())
+    }
+
+    pub(crate) fn unset_route_rewrite(&self, route: &Route) -> Result<()> {
+        if let Some((base, _)) = self.route_rewrite(route) {
+            // Exit code 5 just means there was nothing to unset
+            Command::new("git")
+                .args(["config", "--global", "--unset-all"])
+                .arg(format!("url.{}.insteadOf", base))
+                .status()
+                .context("Failed to run git config")?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn route_list(&self) -> Result<()> {
+        if self.config.routes.is_empty() {
+            println!("📭 No routes found. Use 'gam route add <host/owner/*> <account>' to create one.");
+            return Ok(());
+        }
+        println!("\n🧭 Routes:\n");
+        for route in &self.config.routes {
+            println!("  {} -> {}", route.pattern, route.account);
+        }
+        Ok(())
+    }
+
+    // GIT_SSH_COMMAND="gam ssh": pick the account key for the requested repository, then run ssh
+    pub(crate) fn ssh_wrapper(&self, args: &[String]) -> Result<()> {
+        let mut cmd = Command::new("ssh");
+        if let Some((host, path)) = parse_ssh_invocation(args) {
+            let is_alias = self.account_for_alias(&host).is_some()
+                || self.deprecated_alias_account(&host).is_some();
+            // Aliases already carry their IdentityFile in ~/.ssh/config
+            if !is_alias {
+                if let Some(account) = self.account_for_ssh_target(&host, path.as_deref()) {
+                    for identity_file in account.identity_files() {
+                        cmd.arg("-i").arg(self.ssh_dir.join(identity_file));
+                    }
+                    cmd.arg("-o").arg("IdentitiesOnly=yes");
+                }
+            }
+        }
+        cmd.args(args);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            let err = cmd.exec();
+            Err(err).context("Failed to run ssh")
+        }
+        #[cfg(not(unix))]
+        {
+            let status = cmd.status().context("Failed to run ssh")?;
+            std::process::exit(status.code().unwrap_or(1));
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::githooks::{parse_ssh_remote, remote_url};
+use crate::SshManager;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub(crate) enum InitShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+const BASH_INIT: &str = r#"# gam shell integration (bash)
+_gam_hook() {
+  if [ "$PWD" != "${_GAM_LAST_PWD:-}" ]; then
+    _GAM_LAST_PWD="$PWD"
+    command gam hook cd
+  fi
+}
+case ";${PROMPT_COMMAND:-};" in
+  *";_gam_hook;"*) ;;
+  *) PROMPT_COMMAND="_gam_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
+gam_prompt() {
+  command gam prompt --format "${1:-[{account}] }" 2>/dev/null
+}
+# Show the active identity: PS1='$(gam_prompt)'"$PS1"
+"#;
+
+const ZSH_INIT: &str = r#"# gam shell integration (zsh)
+_gam_hook() {
+  command gam hook cd
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd _gam_hook
+_gam_hook
+gam_prompt() {
+  command gam prompt --format "${1:-[{account}] }" 2>/dev/null
+}
+# Show the active identity: setopt PROMPT_SUBST; PROMPT='$(gam_prompt)'"$PROMPT"
+"#;
+
+const FISH_INIT: &str = r#"# gam shell integration (fish)
+function _gam_hook --on-variable PWD
+    command gam hook cd
+end
+_gam_hook
+function gam_prompt
+    set -l format '[{account}] '
+    if set -q argv[1]
+        set format $argv[1]
+    end
+    command gam prompt --format $format 2>/dev/null
+end
+# Show the active identity: call gam_prompt from fish_prompt
+"#;
+
+// Subcommand paths whose positional argument is an account name
+const ACCOUNT_COMMANDS: &[&str] = &[
+    "switch",
+    "remove",
+    "edit",
+    "rename",
+    "alias set",
+    "bind",
+    "use",
+    "hosts trust",
+    "hosts add",
+    "hosts remove",
+    "agent load",
+    "agent unload",
+    "agent only",
+    "passphrase",
+    "key add",
+    "key remove",
+    "vault store",
+    "vault forget",
+];
+
+// Subcommand paths whose positionals after the first are account names, e.g. `profile add dev work home`
+const ACCOUNT_LIST_COMMANDS: &[&str] = &["profile add", "route add"];
+
+// Static clap completions plus a wrapper completing account names from `gam list --names`
+pub(crate) fn completion_script(shell: Shell) -> String {
+    let mut buf = Vec::new();
+    clap_complete::generate(shell, &mut crate::Args::command(), "gam", &mut buf);
+    let mut script = String::from_utf8_lossy(&buf).into_owned();
+
+    match shell {
+        Shell::Bash => script.push_str(&bash_accounts_completion()),
+        Shell::Zsh => script.push_str(&zsh_accounts_completion()),
+        Shell::Fish => script.push_str(&fish_accounts_completion()),
+        Shell::PowerShell => {
+            // Keep clap's completer as a fallback and register a wrapper in front of it
+            let register = "Register-ArgumentCompleter -Native -CommandName 'gam' -ScriptBlock {";
+            if script.contains(register) {
+                script = script.replacen(register, "$global:__gamClapCompleter = {", 1);
+                script.push_str(&powershell_accounts_completion());
+            }
+        }
+        Shell::Elvish => script.push_str(&elvish_accounts_completion()),
+        _ => {}
+    }
+    script
+}
+
+fn bash_accounts_completion() -> String {
+    let patterns = ACCOUNT_COMMANDS
+        .iter()
+        .map(|command| format!("\"{}\"", command))
+        .chain(ACCOUNT_LIST_COMMANDS.iter().map(|command| format!("\"{} \"?*", command)))
+        .collect::<Vec<_>>()
+        .join("|");
+    format!(
+        r#"
+_gam_accounts() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local cmd_path="${{COMP_WORDS[*]:1:COMP_CWORD-1}}"
+    if [[ "${{cur}}" != -* ]]; then
+        case "${{cmd_path}}" in
+            {patterns})
+                COMPREPLY=( $(compgen -W "$(command gam list --names 2>/dev/null)" -- "${{cur}}") )
+                return 0
+                ;;
+        esac
+    fi
+    _gam "$@"
+}}
+complete -F _gam_accounts -o bashdefault -o default gam
+"#
+    )
+}
+
+fn zsh_accounts_completion() -> String {
+    let patterns = ACCOUNT_COMMANDS
+        .iter()
+        .map(|command| format!("\"{}\"", command))
+        .chain(ACCOUNT_LIST_COMMANDS.iter().map(|command| format!("\"{} \"?*", command)))
+        .collect::<Vec<_>>()
+        .join("|");
+    format!(
+        r#"
+_gam_accounts() {{
+    local cmd_path="${{(j: :)words[2,CURRENT-1]}}"
+    if [[ "$PREFIX" != -* ]]; then
+        case "$cmd_path" in
+            ({patterns})
+                local -a accounts
+                accounts=(${{(f)"$(command gam list --names 2>/dev/null)"}})
+                compadd -a accounts
+                return
+                ;;
+        esac
+    fi
+    _gam "$@"
+}}
+compdef _gam_accounts gam
+"#
+    )
+}
+
+fn fish_accounts_completion() -> String {
+    let mut script = String::from("\n");
+    let list_commands = ACCOUNT_LIST_COMMANDS.iter().map(|command| (command, true));
+    for (command, after_first) in ACCOUNT_COMMANDS.iter().map(|command| (command, false)).chain(list_commands) {
+        let mut condition = command
+            .split(' ')
+            .map(|word| format!("__fish_seen_subcommand_from {}", word))
+            .collect::<Vec<_>>()
+            .join("; and ");
+        if after_first {
+            // "gam", the subcommand words and the first positional come before the accounts
+            condition.push_str(&format!("; and test (count (commandline -opc)) -ge {}", command.split(' ').count() + 2));
+        }
+        script.push_str(&format!(
+            "complete -c gam -n \"{}\" -f -a \"(command gam list --names 2>/dev/null)\"\n",
+            condition
+        ));
+    }
+    script
+}
+
+fn powershell_accounts_completion() -> String {
+    let commands = ACCOUNT_COMMANDS
+        .iter()
+        .map(|command| format!("'{}'", command))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let list_commands = ACCOUNT_LIST_COMMANDS
+        .iter()
+        .map(|command| format!("'{}'", command))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        r#"
+Register-ArgumentCompleter -Native -CommandName 'gam' -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object {{ $_.ToString() }})
+    if ($wordToComplete -ne '' -and $words.Count -gt 0) {{
+        $words = @($words | Select-Object -SkipLast 1)
+    }}
+    $path = $words -join ' '
+    $listPath = @($words | Select-Object -First 2) -join ' '
+    $accounts = (@({commands}) -contains $path) -or ($words.Count -gt 2 -and @({list_commands}) -contains $listPath)
+    if (-not $wordToComplete.StartsWith('-') -and $accounts) {{
+        gam list --names 2>$null | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+            [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_)
+        }}
+        return
+    }}
+    & $global:__gamClapCompleter $wordToComplete $commandAst $cursorPosition
+}}
+"#
+    )
+}
+
+fn elvish_accounts_completion() -> String {
+    let commands = ACCOUNT_COMMANDS
+        .iter()
+        .map(|command| format!("'{}'", command))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let list_commands = ACCOUNT_LIST_COMMANDS
+        .iter()
+        .map(|command| format!("'{}'", command))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        r#"
+var gam-clap-completer = $edit:completion:arg-completer[gam]
+set edit:completion:arg-completer[gam] = {{|@words|
+    var cur = $words[-1]
+    var cmd-path = (str:join ' ' $words[1..-1])
+    var accounts = (has-value [{commands}] $cmd-path)
+    if (> (count $words) 4) {{
+        set accounts = (or $accounts (has-value [{list_commands}] (str:join ' ' $words[1..3])))
+    }}
+    if (and (not (str:has-prefix $cur '-')) $accounts) {{
+        e:gam list --names 2>/dev/null | from-lines
+    }} else {{
+        $gam-clap-completer $@words
+    }}
+}}
+"#
+    )
+}
+
+pub(crate) fn init_script(shell: InitShell) -> &'static str {
+    match shell {
+        InitShell::Bash => BASH_INIT,
+        InitShell::Zsh => ZSH_INIT,
+        InitShell::Fish => FISH_INIT,
+    }
+}
+
+// Absolute form of a directory as stored in `Config.directories`
+fn directory_key(dir: Option<PathBuf>) -> Result<String> {
+    let dir = match dir {
+        Some(dir) => dir,
+        None => env::current_dir().context("Failed to read current directory")?,
+    };
+    let dir = fs::canonicalize(&dir)
+        .with_context(|| format!("Directory {} does not exist", dir.display()))?;
+    Ok(dir.display().to_string())
+}
+
+impl SshManager {
+    // Account bound to `dir` or its closest bound ancestor
+    pub(crate) fn bound_account_for(&self, dir: &Path) -> Option<&str> {
+        dir.ancestors()
+            .find_map(|ancestor| self.config.directories.get(&ancestor.display().to_string()))
+            .map(|account| account.as_str())
+    }
+
+    // Account in effect for the current directory: its binding, else the only active account
+    pub(crate) fn effective_account(&self) -> Option<&str> {
+        env::current_dir()
+            .ok()
+            .and_then(|cwd| self.bound_account_for(&cwd))
+            .or_else(|| match self.active_account_names().as_slice() {
+                [only] => Some(*only),
+                _ => None,
+            })
+    }
+
+    // Account in effect for the current repository: the directory binding, else the account ssh
+    // uses for its SSH remote (or for `pinned_host` without one), else the only active account
+    pub(crate) fn effective_account_for_repo(&self, pinned_host: Option<&str>) -> Option<&str> {
+        if let Some(bound) = env::current_dir().ok().and_then(|cwd| self.bound_account_for(&cwd)) {
+            return Some(bound);
+        }
+        let target = remote_url(None)
+            .as_deref()
+            .and_then(parse_ssh_remote)
+            .map(|remote| (remote.host, Some(remote.path)))
+            .or_else(|| pinned_host.map(|host| (host.to_string(), None)));
+        match target {
+            // Another host's active account says nothing about this repository
+            Some((host, path)) => self.account_for_ssh_target(&host, path.as_deref()).map(|account| account.name.as_str()),
+            None => self.effective_account(),
+        }
+    }
+
+    pub(crate) fn bind_directory(&mut self, account: &str, dir: Option<PathBuf>) -> Result<()> {
+        if !self.config.accounts.contains_key(account) {
+            return Err(anyhow::anyhow!("Account '{}' not found", account));
+        }
+        let key = directory_key(dir)?;
+        self.config.directories.insert(key.clone(), account.to_string());
+        self.save_config().context("Failed to save configuration")?;
+        println!("✅ {} is now bound to account '{}'", key, account);
+        Ok(())
+    }
+
+    pub(crate) fn unbind_directory(&mut self, dir: Option<PathBuf>) -> Result<()> {
+        let key = directory_key(dir)?;
+        if self.config.directories.remove(&key).is_some() {
+            self.save_config().context("Failed to save configuration")?;
+            println!("✅ Binding for {} removed", key);
+        } else {
+            println!("ℹ️  {} is not bound to any account", key);
+        }
+        Ok(())
+    }
+
+    // Kept free of network and ssh calls: it runs on every prompt
+    pub(crate) fn print_prompt(&self, format: &str) -> Result<()> {
+        // With accounts active on several hosts (e.g. a profile), show them all
+        let account = match self.effective_account() {
+            Some(account) => account.to_string(),
+            None => self.active_account_names().join("+"),
+        };
+        if !account.is_empty() {
+            print!("{}", format.replace("{account}", &account));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn hook_cd(&mut self) -> Result<()> {
+        let cwd = env::current_dir().context("Failed to read current directory")?;
+        let Some(bound) = self.bound_account_for(&cwd).map(|account| account.to_string()) else {
+            return Ok(());
+        };
+        let Some(account) = self.config.accounts.get(&bound) else {
+            eprintln!("⚠️  gam: {} is bound to unknown account '{}'", cwd.display(), bound);
+            return Ok(());
+        };
+        if self.is_fully_active(account) {
+            return Ok(());
+        }
+
+        self.activate_account_quietly(&bound)?;
+        println!("🔄 gam: switched to account '{}'", bound);
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+// Account names end up in key file names, `Host` lines, config comments and regexes
+const MAX_NAME_LEN: usize = 64;
+const MAX_KEY_LABEL_LEN: usize = 32;
+const MAX_DESCRIPTION_LEN: usize = 200;
+const MAX_SSH_USER_LEN: usize = 64;
+// RFC 5321 limits
+const MAX_EMAIL_LEN: usize = 254;
+const MAX_LOCAL_PART_LEN: usize = 64;
+// RFC 1035 limits
+const MAX_HOSTNAME_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+
+pub(crate) fn validate_account_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Account name cannot be empty".to_string());
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(format!("Account name must be at most {} characters", MAX_NAME_LEN));
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return Err("Account name must start with a letter or digit".to_string());
+    }
+    if let Some(c) = name.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))) {
+        return Err(format!(
+            "Account name cannot contain {:?}; use letters, digits, '-', '_' or '.'",
+            c
+        ));
+    }
+    if name.contains("..") {
+        return Err("Account name cannot contain '..'".to_string());
+    }
+    Ok(())
+}
+
+// Labels become part of generated key file names
+pub(crate) fn validate_key_label(label: &str) -> Result<(), String> {
+    if label.is_empty() || label.len() > MAX_KEY_LABEL_LEN {
+        return Err(format!("Key label must be 1-{} characters", MAX_KEY_LABEL_LEN));
+    }
+    if let Some(c) = label.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))) {
+        return Err(format!("Key label cannot contain {:?}; use letters, digits, '-' or '_'", c));
+    }
+    Ok(())
+}
+
+// "owner/repo", with GitLab subgroups allowed in between
+pub(crate) fn validate_repository(repository: &str) -> Result<(), String> {
+    let segments: Vec<&str> = repository.split('/').collect();
+    if segments.len() < 2 {
+        return Err(format!("'{}' must look like owner/repo", repository));
+    }
+    for segment in segments {
+        if segment.is_empty() || segment == "." || segment == ".." {
+            return Err(format!("'{}' has an empty or relative path segment", repository));
+        }
+        if let Some(c) = segment.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))) {
+            return Err(format!("'{}' cannot contain {:?}", repository, c));
+        }
+    }
+    Ok(())
+}
+
+// RFC 1123 hostname: dot-separated labels of letters, digits and inner hyphens
+fn validate_hostname(host: &str) -> Result<(), String> {
+    if host.is_empty() {
+        return Err("Host cannot be empty".to_string());
+    }
+    let host = host.strip_suffix('.').unwrap_or(host);
+    if host.len() > MAX_HOSTNAME_LEN {
+        return Err(format!("Host must be at most {} characters", MAX_HOSTNAME_LEN));
+    }
+    for label in host.split('.') {
+        if label.is_empty() {
+            return Err(format!("'{}' has an empty label", host));
+        }
+        if label.len() > MAX_LABEL_LEN {
+            return Err(format!("'{}' has a label longer than {} characters", host, MAX_LABEL_LEN));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("Labels in '{}' cannot start or end with '-'", host));
+        }
+        if let Some(c) = label.chars().find(|c| !(c.is_ascii_alphanumeric() || *c == '-')) {
+            return Err(format!("'{}' is not a valid hostname (contains {:?})", host, c));
+        }
+    }
+    Ok(())
+}
+
+fn parse_port(port: &str) -> Result<u16, String> {
+    match port.parse::<u16>() {
+        Ok(0) | Err(_) => Err(format!("'{}' is not a valid port (1-65535)", port)),
+        Ok(port) => Ok(port),
+    }
+}
+
+// "host", "host:port", "1.2.3.4:22", "::1" or "[::1]:22" -> (host, port)
+pub(crate) fn split_host_port(input: &str) -> Result<(String, Option<u16>), String> {
+    if let Some(rest) = input.strip_prefix('[') {
+        let (address, after) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("'{}' is missing a closing ']'", input))?;
+        address
+            .parse::<Ipv6Addr>()
+            .map_err(|_| format!("'{}' is not a valid IPv6 address", address))?;
+        let port = match after {
+            "" => None,
+            _ => Some(parse_port(after.strip_prefix(':').ok_or_else(|| {
+                format!("Unexpected '{}' after the IPv6 address", after)
+            })?)?),
+        };
+        return Ok((address.to_string(), port));
+    }
+    if input.parse::<Ipv6Addr>().is_ok() {
+        return Ok((input.to_string(), None));
+    }
+
+    let (host, port) = match input.rsplit_once(':') {
+        Some((host, port)) => (host, Some(parse_port(port)?)),
+        None => (input, None),
+    };
+    if host.parse::<Ipv4Addr>().is_err() {
+        // All-numeric dotted names would be misread as (invalid) IPv4 addresses
+        if host.split('.').all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_digit())) {
+            return Err(format!("'{}' is not a valid IPv4 address", host));
+        }
+        validate_hostname(host)?;
+    }
+    Ok((host.to_string(), port))
+}
+
+pub(crate) fn validate_host(host: &str) -> Result<(), String> {
+    split_host_port(host).map(|_| ())
+}
+
+// Hosts written to `Host`/`HostName` lines, where a port has its own option
+pub(crate) fn validate_host_without_port(host: &str) -> Result<(), String> {
+    match split_host_port(host)? {
+        (_, Some(_)) => Err(format!("'{}' includes a port; enter the port separately", host)),
+        (_, None) => Ok(()),
+    }
+}
+
+// Written to `User` lines and ProxyJump hops
+pub(crate) fn validate_ssh_user(user: &str) -> Result<(), String> {
+    if user.is_empty() || user.len() > MAX_SSH_USER_LEN {
+        return Err(format!("SSH user must be 1-{} characters", MAX_SSH_USER_LEN));
+    }
+    if user.starts_with('-') {
+        return Err("SSH user cannot start with '-'".to_string());
+    }
+    if let Some(c) = user.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))) {
+        return Err(format!("SSH user cannot contain {:?}; use letters, digits, '-', '_' or '.'", c));
+    }
+    Ok(())
+}
+
+// Comma-separated "[user@]host[:port]" hops, as ssh -J takes them
+pub(crate) fn validate_proxy_jump(proxy_jump: &str) -> Result<(), String> {
+    for hop in proxy_jump.split(',') {
+        if hop.is_empty() {
+            return Err(format!("'{}' has an empty hop", proxy_jump));
+        }
+        let host = match hop.split_once('@') {
+            Some((user, host)) => {
+                validate_ssh_user(user)?;
+                host
+            }
+            None => hop,
+        };
+        validate_host(host)?;
+    }
+    Ok(())
+}
+
+// Extra ssh_config option values share the option's line; a line break would start a new directive
+pub(crate) fn validate_ssh_option_value(value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err("Option value cannot be empty".to_string());
+    }
+    if value.chars().any(|c| c.is_control() && c != '\t') {
+        return Err("Option value cannot contain line breaks or control characters".to_string());
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_description(description: &str) -> Result<(), String> {
+    if description.chars().count() > MAX_DESCRIPTION_LEN {
+        return Err(format!("Description must be at most {} characters", MAX_DESCRIPTION_LEN));
+    }
+    if description.chars().any(|c| c.is_control()) {
+        return Err("Description cannot contain line breaks or control characters".to_string());
+    }
+    Ok(())
+}
+
+// Dot-atom addresses (RFC 5322) with a hostname domain; quoted local parts are not accepted
+pub(crate) fn validate_email(email: &str) -> Result<(), String> {
+    if email.len() > MAX_EMAIL_LEN {
+        return Err(format!("Email must be at most {} characters", MAX_EMAIL_LEN));
+    }
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return Err(format!("'{}' is missing an '@'", email));
+    };
+    if local.is_empty() || local.len() > MAX_LOCAL_PART_LEN {
+        return Err(format!("The part before '@' must be 1-{} characters", MAX_LOCAL_PART_LEN));
+    }
+    if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+        return Err(format!("'{}' has misplaced dots before the '@'", email));
+    }
+    if let Some(c) = local.chars().find(|c| !(c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(*c))) {
+        return Err(format!("'{}' contains {:?} before the '@'", email, c));
+    }
+
+    validate_hostname(domain).map_err(|e| format!("Invalid email domain: {}", e))?;
+    let tld = domain.rsplit('.').next().unwrap_or_default();
+    if !domain.contains('.') || tld.len() < 2 || !tld.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!("'{}' needs a domain like example.com", email));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_typical_account_names() {
+        for name in ["work", "personal", "github-work", "acme_corp", "team.2", "W0rk"] {
+            assert!(validate_account_name(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_account_names_that_escape_or_break_config() {
+        for name in ["", "../evil", "a/b", "a\\b", "a..b", "two words", "star*", "new\nline", ".hidden", "-flag"] {
+            assert!(validate_account_name(name).is_err(), "{:?}", name);
+        }
+        assert!(validate_account_name(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn key_labels_are_file_name_safe() {
+        for label in ["laptop", "signing-2", "ci_deploy"] {
+            assert!(validate_key_label(label).is_ok(), "{}", label);
+        }
+        for label in ["", "a/b", "..", "two words", "x.y"] {
+            assert!(validate_key_label(label).is_err(), "{:?}", label);
+        }
+        assert!(validate_key_label(&"k".repeat(MAX_KEY_LABEL_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn repositories_need_owner_and_name() {
+        for repository in ["acme/api", "acme/platform/api", "me/dot.files"] {
+            assert!(validate_repository(repository).is_ok(), "{}", repository);
+        }
+        for repository in ["api", "acme/", "/api", "acme/../api", "acme/a b", "acme/api?x"] {
+            assert!(validate_repository(repository).is_err(), "{:?}", repository);
+        }
+    }
+
+    #[test]
+    fn splits_hosts_and_ports() {
+        assert_eq!(split_host_port("github.com"), Ok(("github.com".to_string(), None)));
+        assert_eq!(split_host_port("git.corp.example:8443"), Ok(("git.corp.example".to_string(), Some(8443))));
+        assert_eq!(split_host_port("10.0.0.5:2222"), Ok(("10.0.0.5".to_string(), Some(2222))));
+        assert_eq!(split_host_port("[::1]:22"), Ok(("::1".to_string(), Some(22))));
+        assert_eq!(split_host_port("fe80::1"), Ok(("fe80::1".to_string(), None)));
+        assert_eq!(split_host_port("localhost"), Ok(("localhost".to_string(), None)));
+    }
+
+    #[test]
+    fn rejects_invalid_hosts() {
+        for host in [
+            "", "git hub.com", "github.com/org", "-bad.com", "bad-.com", "a..com", "host:0",
+            "host:70000", "host:", "999.1.1.1", "[::1", "[nope]:22", "*.example.com", "evil\nHost *",
+        ] {
+            assert!(validate_host(host).is_err(), "{:?}", host);
+        }
+        assert!(validate_host(&format!("{}.com", "a".repeat(MAX_LABEL_LEN + 1))).is_err());
+    }
+
+    #[test]
+    fn host_without_port_rejects_ports() {
+        assert!(validate_host_without_port("git.corp.example").is_ok());
+        assert!(validate_host_without_port("git.corp.example:2222").is_err());
+    }
+
+    #[test]
+    fn ssh_users_are_single_words() {
+        for user in ["git", "gitlab", "svc.deploy", "build_bot-2"] {
+            assert!(validate_ssh_user(user).is_ok(), "{}", user);
+        }
+        for user in ["", "two words", "git\nHost *", "-oProxyCommand", "me@host", "tab\tuser"] {
+            assert!(validate_ssh_user(user).is_err(), "{:?}", user);
+        }
+        assert!(validate_ssh_user(&"u".repeat(MAX_SSH_USER_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn proxy_jumps_are_lists_of_hosts() {
+        for proxy_jump in ["bastion.example.com", "ops@bastion:2222", "jump1,admin@jump2.corp.example", "[::1]:22"] {
+            assert!(validate_proxy_jump(proxy_jump).is_ok(), "{}", proxy_jump);
+        }
+        for proxy_jump in [
+            "", "bastion,", ",bastion", "bastion jump2", "bad user@bastion", "@bastion", "bastion:0",
+            "bastion\n    IdentityFile /tmp/x",
+        ] {
+            assert!(validate_proxy_jump(proxy_jump).is_err(), "{:?}", proxy_jump);
+        }
+    }
+
+    #[test]
+    fn option_values_stay_on_one_line() {
+        for value in ["60", "ssh -W %h:%p bastion", "yes\t# trailing tab"] {
+            assert!(validate_ssh_option_value(value).is_ok(), "{:?}", value);
+        }
+        for value in ["", "  ", "60\nHost *\n  IdentityFile /tmp/x", "60\r", "a\0b"] {
+            assert!(validate_ssh_option_value(value).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn descriptions_are_single_line() {
+        assert!(validate_description("").is_ok());
+        assert!(validate_description("Work laptop – ACME").is_ok());
+        assert!(validate_description("line\nHost *").is_err());
+        assert!(validate_description("tab\there").is_err());
+        assert!(validate_description(&"x".repeat(MAX_DESCRIPTION_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn accepts_valid_emails() {
+        for email in ["me@example.com", "first.last+git@mail.acme.co.uk", "o'neil@example.org", "x_y-z@sub-domain.io"] {
+            assert!(validate_email(email).is_ok(), "{}", email);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_emails() {
+        for email in [
+            "", "plain", "@example.com", "me@", "me@localhost", "me@example.c", "me@example.123",
+            ".me@example.com", "me.@example.com", "m..e@example.com", "me@-example.com",
+            "me@exa mple.com", "me@@example.com", "me\n@example.com",
+        ] {
+            assert!(validate_email(email).is_err(), "{:?}", email);
+        }
+    }
+}
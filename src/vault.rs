@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use inquire::Password;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use crate::keyring::{keyring_accounts, keyring_available, keyring_clear, keyring_store};
+use crate::SshManager;
+
+// Lets scripts and helpers unlock the vault without a prompt
+pub(crate) const VAULT_PASSWORD_ENV: &str = "GAM_VAULT_PASSWORD";
+// Marks the gam started by ssh-add as its SSH_ASKPASS program
+pub(crate) const ASKPASS_ENV: &str = "GAM_ASKPASS";
+// Passphrase handed to that askpass run by the gam that started ssh-add
+pub(crate) const ASKPASS_SECRET_ENV: &str = "GAM_ASKPASS_SECRET";
+// New passphrase for ssh-keygen when it creates or rewraps a key
+pub(crate) const ASKPASS_NEW_SECRET_ENV: &str = "GAM_ASKPASS_NEW_SECRET";
+
+// On-disk layout; secret names stay readable so gam can tell what is stored without unlocking
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    names: Vec<String>,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// Secrets encrypted with XChaCha20-Poly1305 under an Argon2id key derived from a master password
+pub(crate) struct Vault {
+    path: PathBuf,
+    password: String,
+    secrets: BTreeMap<String, String>,
+}
+
+pub(crate) fn token_key(account: &str) -> String {
+    format!("token:{}", account)
+}
+
+pub(crate) fn passphrase_key(account: &str) -> String {
+    format!("passphrase:{}", account)
+}
+
+// Either started by `feed_passphrase`, or through a `gam-askpass` link set as SSH_ASKPASS by hand
+pub(crate) fn invoked_as_askpass() -> bool {
+    env::var_os(ASKPASS_ENV).is_some()
+        || env::args_os()
+            .next()
+            .map(PathBuf::from)
+            .and_then(|exe| exe.file_stem().map(|stem| stem == "gam-askpass"))
+            .unwrap_or(false)
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Failed to derive vault key: {}", e))?;
+    Ok(key)
+}
+
+impl Vault {
+    pub(crate) fn path(ssh_dir: &Path) -> PathBuf {
+        ssh_dir.join("gam").join("vault.json")
+    }
+
+    fn read_file(path: &Path) -> Result<Option<VaultFile>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path).context("Failed to read vault")?;
+        let file = serde_json::from_str(&content).context("Failed to parse vault")?;
+        Ok(Some(file))
+    }
+
+    // Whether a secret is stored, answered without asking for the master password
+    pub(crate) fn contains(ssh_dir: &Path, name: &str) -> bool {
+        Self::read_file(&Self::path(ssh_dir))
+            .ok()
+            .flatten()
+            .is_some_and(|file| file.names.iter().any(|n| n == name))
+    }
+
+    // Names of the stored secrets, e.g. "token:work"
+    pub(crate) fn names(ssh_dir: &Path) -> Vec<String> {
+        Self::read_file(&Self::path(ssh_dir))
+            .ok()
+            .flatten()
+            .map(|file| file.names)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn open(ssh_dir: &Path) -> Result<Vault> {
+        let path = Self::path(ssh_dir);
+        let Some(file) = Self::read_file(&path)? else {
+            let password = match env::var(VAULT_PASSWORD_ENV) {
+                Ok(password) if !password.is_empty() => password,
+                _ => Password::new("Create a master password for the gam vault:")
+                    .with_custom_confirmation_message("Confirm master password:")
+                    .prompt()
+                    .context("Failed to get master password")?,
+            };
+            return Ok(Vault { path, password, secrets: BTreeMap::new() });
+        };
+
+        let password = match env::var(VAULT_PASSWORD_ENV) {
+            Ok(password) if !password.is_empty() => password,
+            _ => Password::new("gam vault master password:")
+                .without_confirmation()
+                .prompt()
+                .context("Failed to get master password")?,
+        };
+
+        let salt = BASE64.decode(&file.salt).context("Corrupted vault salt")?;
+        let nonce = BASE64.decode(&file.nonce).context("Corrupted vault nonce")?;
+        let ciphertext = BASE64.decode(&file.ciphertext).context("Corrupted vault data")?;
+        if nonce.len() != 24 {
+            return Err(anyhow::anyhow!("Corrupted vault nonce"));
+        }
+
+        let key = derive_key(&password, &salt)?;
+        let cipher = XChaCha20Poly1305::new(&key.into());
+        let plaintext = cipher
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| anyhow::anyhow!("Wrong master password or corrupted vault"))?;
+        let secrets = serde_json::from_slice(&plaintext).context("Failed to parse vault contents")?;
+
+        Ok(Vault { path, password, secrets })
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(|secret| secret.as_str())
+    }
+
+    pub(crate) fn set(&mut self, name: &str, secret: &str) {
+        self.secrets.insert(name.to_string(), secret.to_string());
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        self.secrets.remove(name).is_some()
+    }
+
+    // Drop a secret if stored; unlocking is only asked for when there is something to remove
+    pub(crate) fn discard(ssh_dir: &Path, name: &str) -> Result<()> {
+        if !Self::contains(ssh_dir, name) {
+            return Ok(());
+        }
+        let mut vault = Self::open(ssh_dir)?;
+        vault.remove(name);
+        vault.save()
+    }
+
+    // Re-encrypts everything with a fresh salt and nonce
+    pub(crate) fn save(&self) -> Result<()> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(&self.password, &salt)?;
+        let cipher = XChaCha20Poly1305::new(&key.into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let plaintext = serde_json::to_vec(&self.secrets).context("Failed to serialize vault")?;
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_ref())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt vault"))?;
+
+        let file = VaultFile {
+            version: 1,
+            names: self.secrets.keys().cloned().collect(),
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).context("Failed to create vault directory")?;
+            #[cfg(unix)]
+            {
+                let perms = fs::Permissions::from_mode(0o700);
+                fs::set_permissions(dir, perms).context("Failed to set vault directory permissions")?;
+            }
+        }
+        let json = serde_json::to_string_pretty(&file).context("Failed to serialize vault")?;
+        fs::write(&self.path, json).context("Failed to write vault")?;
+        #[cfg(unix)]
+        {
+            let perms = fs::Permissions::from_mode(0o600);
+            fs::set_permissions(&self.path, perms).context("Failed to set vault permissions to 600")?;
+        }
+        Ok(())
+    }
+}
+
+impl SshManager {
+    pub(crate) fn vault_status(&self) -> Result<()> {
+        let in_keyring = keyring_accounts();
+        if !in_keyring.is_empty() {
+            println!("\n🔐 Desktop keyring (Secret Service):\n");
+            for account in &in_keyring {
+                println!("  🔑 key passphrase for '{}'", account);
+            }
+        }
+
+        let path = Vault::path(&self.ssh_dir);
+        if !path.exists() {
+            if in_keyring.is_empty() {
+                println!("📭 No vault yet. Use 'gam vault store <account>' or 'gam add --https' to create one.");
+            }
+            return Ok(());
+        }
+
+        println!("\n🔐 Vault: {}\n", path.display());
+        let names = Vault::names(&self.ssh_dir);
+        if names.is_empty() {
+            println!("  (empty)");
+        }
+        for name in names {
+            match name.split_once(':') {
+                Some(("passphrase", account)) => println!("  🔑 key passphrase for '{}'", account),
+                Some(("token", account)) => println!("  🌐 HTTPS token for '{}'", account),
+                _ => println!("  ❔ {}", name),
+            }
+        }
+        Ok(())
+    }
+
+    // Save an existing key's passphrase so `gam agent load` can unlock it unattended
+    pub(crate) fn vault_store(&self, account_name: &str, keyring: bool) -> Result<()> {
+        let account = self.config.accounts.get(account_name)
+            .with_context(|| format!("Account '{}' not found", account_name))?;
+        let key_path = self.ssh_dir.join(&account.key_file);
+
+        let passphrase = Password::new(&format!("Passphrase for {}:", key_path.display()))
+            .without_confirmation()
+            .prompt()
+            .context("Failed to get passphrase")?;
+        if !Self::passphrase_unlocks(&key_path, &passphrase) {
+            return Err(anyhow::anyhow!("That passphrase does not unlock {}", key_path.display()));
+        }
+
+        self.store_passphrase(account_name, &passphrase, keyring)
+    }
+
+    pub(crate) fn store_passphrase(&self, account_name: &str, passphrase: &str, keyring: bool) -> Result<()> {
+        if keyring {
+            if !keyring_available() {
+                return Err(anyhow::anyhow!("No Secret Service found (needs secret-tool and a D-Bus session)"));
+            }
+            keyring_store(account_name, passphrase)?;
+            println!("✅ Passphrase for '{}' stored in the desktop keyring", account_name);
+            return Ok(());
+        }
+
+        let mut vault = Vault::open(&self.ssh_dir)?;
+        vault.set(&passphrase_key(account_name), passphrase);
+        vault.save()?;
+        println!("✅ Passphrase for '{}' stored in the encrypted vault", account_name);
+        Ok(())
+    }
+
+    // Remove an account's passphrase from both the keyring and the vault
+    pub(crate) fn vault_forget(&self, account_name: &str) -> Result<()> {
+        let mut removed = false;
+        if keyring_clear(account_name) {
+            removed = true;
+            println!("✅ Passphrase for '{}' removed from the desktop keyring", account_name);
+        }
+        let key = passphrase_key(account_name);
+        if Vault::contains(&self.ssh_dir, &key) {
+            Vault::discard(&self.ssh_dir, &key)?;
+            removed = true;
+            println!("✅ Passphrase for '{}' removed from the vault", account_name);
+        }
+        if !removed {
+            println!("ℹ️  No passphrase stored for '{}'", account_name);
+        }
+        Ok(())
+    }
+
+    // `SSH_ASKPASS` program: answer ssh-add's passphrase prompt from the keyring or vault
+    pub(crate) fn askpass(&self, prompt: Option<&str>) -> Result<()> {
+        let prompt = prompt.unwrap_or_default();
+        // A stored passphrase that was rejected would be offered forever; give up instead
+        if prompt.starts_with("Bad passphrase") {
+            return Err(anyhow::anyhow!("Stored passphrase was rejected"));
+        }
+
+        // ssh-keygen asks "Enter new passphrase (empty for no passphrase): " and "Enter same passphrase again: "
+        let asks_new = prompt.contains("new passphrase")
+            || prompt.contains("empty for no passphrase")
+            || prompt.contains("same passphrase again");
+        if asks_new {
+            if let Ok(secret) = env::var(ASKPASS_NEW_SECRET_ENV) {
+                println!("{}", secret);
+                return Ok(());
+            }
+        }
+        if let Ok(secret) = env::var(ASKPASS_SECRET_ENV) {
+            println!("{}", secret);
+            return Ok(());
+        }
+
+        // ssh-add asks "Enter passphrase for /home/me/.ssh/id_work (will confirm each use): "
+        let requested = prompt
+            .strip_prefix("Enter passphrase for ")
+            .map(|rest| rest.split(" (").next().unwrap_or(rest).trim_end().trim_end_matches(':'))
+            .unwrap_or_default();
+        let account = self.config.accounts.values()
+            .find(|account| {
+                Self::account_keys(account).iter().any(|(_, file, _)| self.ssh_dir.join(file) == Path::new(requested))
+            });
+        let Some(account) = account else {
+            return Err(anyhow::anyhow!("No gam account matches the prompt '{}'", prompt.trim()));
+        };
+
+        let passphrase = self.stored_passphrase(&account.name)?
+            .with_context(|| format!("No passphrase stored for '{}'", account.name))?;
+        println!("{}", passphrase);
+        Ok(())
+    }
+}